@@ -1,12 +1,37 @@
 //! This module handles the GUI representation of the program
 
-use crate::{filtering::ParsingError, vault_parser::vault_to_graph, GraphView, Page};
+use crate::{
+    filtering::ParsingError,
+    vault_parser::{
+        extract_pages_with_progress, format::VaultFormat, pages_to_graph, roam,
+        scanner::ScanConfig, update_pages, watcher::VaultWatcher, ParseReport, Progress,
+        VaultError,
+    },
+    Edge, EdgeFilter, GraphView, LinkKind, Page,
+};
 use eframe::egui;
 use petgraph::{
     // dot::{Config, Dot},
-    graph::NodeIndex,
+    graph::{EdgeIndex, NodeIndex},
     Graph,
 };
+use std::{
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+type LoadResult = Result<(Graph<Page, Edge>, ParseReport), VaultError>;
+type UpdateResult = Result<(Vec<Page>, ParseReport), VaultError>;
+
+// Number of links listed in the tooltip of a hovered edge
+const MAX_EDGE_TOOLTIP_LINES: usize = 8;
+
+// A vault being parsed on a background thread
+struct VaultLoading {
+    progress: Arc<Mutex<Progress>>,
+    result: mpsc::Receiver<LoadResult>,
+}
 
 pub struct MyApp {
     /// The graph currently being viewd
@@ -21,6 +46,8 @@ pub struct MyApp {
     hovering_node: Option<NodeIndex>,
     /// The number of frames while which a node is being hovered over
     node_hover_time: f32,
+    /// If an edge is currently being hovered over (while no node is)
+    hovering_edge: Option<EdgeIndex>,
     /// Current zoom level
     zoom: f32,
     /// Zoom sensitivity
@@ -65,14 +92,40 @@ pub struct MyApp {
     filter_query: String,
     /// Error encountered when parsing filtering expression (if any)
     filtering_error: Option<ParsingError>,
+    /// Configures which edges are shown
+    edge_filter: EdgeFilter,
+    /// Directory of the current vault (if any)
+    vault_dir: Option<PathBuf>,
+    /// Configures which files of a vault are scanned
+    scan_config: ScanConfig,
+    /// Gitignore-style include patterns of the scan config (one per line)
+    include_patterns: String,
+    /// Gitignore-style exclude patterns of the scan config (one per line)
+    exclude_patterns: String,
+    /// Problems encountered when parsing the current vault
+    parse_report: ParseReport,
+    /// Error encountered when loading a vault (if any)
+    vault_error: Option<VaultError>,
+    /// The vault currently being loaded in the background (if any)
+    loading: Option<VaultLoading>,
+    /// The pages of changed files currently being parsed in the background (if any)
+    updating: Option<mpsc::Receiver<UpdateResult>>,
+    /// Whether to update the graph when files of the vault change
+    watch_vault: bool,
+    /// Watches the current vault for changes (if enabled)
+    watcher: Option<VaultWatcher>,
 }
 
 impl MyApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>, graph: Graph<Page, ()>) -> Self {
+    pub fn new(_cc: &eframe::CreationContext<'_>, graph: Graph<Page, Edge>) -> Self {
         // Customize egui here with cc.egui_ctx.set_fonts and cc.egui_ctx.set_visuals.
         // Restore app state using cc.storage (requires the "persistence" feature).
         // Use the cc.gl (a glow::Context) to create graphics shaders and buffers that you can use
         // for e.g. egui::PaintCallback.
+        let scan_config = ScanConfig {
+            use_cache: true,
+            ..Default::default()
+        };
 
         Self {
             graphview: GraphView::new(graph),
@@ -81,6 +134,7 @@ impl MyApp {
             dragging_node: None,
             hovering_node: None,
             node_hover_time: 0.,
+            hovering_edge: None,
             zoom: 1.0,
             zoom_step: 0.15,
             draw_arrows: false,
@@ -105,6 +159,150 @@ impl MyApp {
 
             filter_query: String::default(),
             filtering_error: None,
+            edge_filter: EdgeFilter::default(),
+            vault_dir: None,
+            include_patterns: scan_config.include.join("\n"),
+            exclude_patterns: scan_config.exclude.join("\n"),
+            scan_config,
+            parse_report: ParseReport::default(),
+            vault_error: None,
+            loading: None,
+            updating: None,
+            watch_vault: true,
+            watcher: None,
+        }
+    }
+
+    /// Start loading a vault in the background, replacing the current graph once it is parsed
+    pub fn load_vault(&mut self, vault_dir: &Path) {
+        // The format of a newly opened vault is detected, but can be changed before reloading it
+        if self.vault_dir.as_deref() != Some(vault_dir) {
+            self.scan_config.format = VaultFormat::detect(vault_dir);
+        }
+        self.vault_dir = Some(vault_dir.to_path_buf());
+
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        let (sender, receiver) = mpsc::channel();
+        let vault_dir = vault_dir.to_path_buf();
+        let config = self.scan_config.clone();
+        let thread_progress = Arc::clone(&progress);
+
+        thread::spawn(move || {
+            let result = extract_pages_with_progress(&vault_dir, &config, |update| {
+                if let Ok(mut progress) = thread_progress.lock() {
+                    // Updates from parallel workers may arrive out of order
+                    if update.parsed_files >= progress.parsed_files {
+                        *progress = update;
+                    }
+                }
+            })
+            .map(|(pages, report)| (pages_to_graph(pages), report));
+
+            // The receiver is gone if another vault was loaded in the meantime
+            sender.send(result).ok();
+        });
+
+        // Dropping a previous receiver discards the result of its (now outdated) load or update
+        self.loading = Some(VaultLoading {
+            progress,
+            result: receiver,
+        });
+        self.updating = None;
+
+        // Changes made while the vault is loading are applied once it is loaded
+        self.update_watcher();
+    }
+
+    // Start or stop watching the current vault for changes
+    fn update_watcher(&mut self) {
+        self.watcher = None;
+
+        if let (Some(vault_dir), true) = (&self.vault_dir, self.watch_vault) {
+            // Imported exports (such as a Roam JSON file) are not watched
+            if !vault_dir.is_dir() {
+                return;
+            }
+
+            match VaultWatcher::new(vault_dir, &self.scan_config) {
+                Ok(watcher) => self.watcher = Some(watcher),
+                Err(error) => {
+                    self.vault_error = Some(VaultError::Unwatchable(vault_dir.clone(), error))
+                }
+            }
+        }
+    }
+
+    // Re-parse the files of the vault which changed on a background thread, patching the graph
+    // in place once they are parsed
+    fn poll_vault_changes(&mut self, ctx: &egui::Context) {
+        if let Some(updating) = &self.updating {
+            match updating.try_recv() {
+                Ok(Ok((pages, report))) => {
+                    self.graphview.update_pages(pages);
+                    self.parse_report = report;
+                    self.filtering_error = self.graphview.filter_nodes(&self.filter_query);
+                    self.updating = None;
+                }
+                Ok(Err(error)) => {
+                    self.vault_error = Some(error);
+                    self.updating = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint();
+                    return;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.updating = None,
+            }
+        }
+
+        let (Some(watcher), Some(vault_dir), None) =
+            (&self.watcher, &self.vault_dir, &self.loading)
+        else {
+            return;
+        };
+
+        // Changes made during an update are picked up once it is done
+        let changed_paths = watcher.changed_paths();
+        if changed_paths.is_empty() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let vault_dir = vault_dir.clone();
+        let config = self.scan_config.clone();
+        let pages = self.graphview.pages();
+        let report = self.parse_report.clone();
+
+        thread::spawn(move || {
+            let result = update_pages(&vault_dir, &config, pages, &report, &changed_paths);
+            sender.send(result).ok();
+        });
+
+        self.updating = Some(receiver);
+        ctx.request_repaint();
+    }
+
+    // Replace the current graph once the vault loading in the background is done
+    fn poll_vault_loading(&mut self, ctx: &egui::Context) {
+        let Some(loading) = &self.loading else {
+            return;
+        };
+
+        match loading.result.try_recv() {
+            Ok(Ok((graph, report))) => {
+                self.graphview = GraphView::new(graph);
+                self.graphview.set_edge_filter(self.edge_filter.clone());
+                self.filtering_error = self.graphview.filter_nodes(&self.filter_query);
+                self.parse_report = report;
+                self.vault_error = None;
+                self.loading = None;
+            }
+            Ok(Err(error)) => {
+                self.vault_error = Some(error);
+                self.loading = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => ctx.request_repaint(),
+            Err(mpsc::TryRecvError::Disconnected) => self.loading = None,
         }
     }
 }
@@ -114,10 +312,14 @@ impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Look for drag-and-dropped files
         self.ui_file_drag_and_drop(ctx);
+        self.poll_vault_loading(ctx);
+        self.poll_vault_changes(ctx);
 
         // Top panel
         egui::TopBottomPanel::top("menu_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
+                // The entries of the menus are placeholders, which do nothing yet
+                #[allow(clippy::needless_ifs)]
                 ui.menu_button("File", |ui| {
                     if ui.button("New File").clicked() {}
                     if ui.button("Open File...").clicked() {}
@@ -140,6 +342,26 @@ impl eframe::App for MyApp {
                     );
                     if ui.button("Documentation").clicked() {}
                 });
+
+                // Show the progress of a vault loading in the background
+                if let Some(loading) = &self.loading {
+                    let progress = loading.progress.lock().map(|p| *p).unwrap_or_default();
+                    let fraction = if progress.total_files > 0 {
+                        progress.parsed_files as f32 / progress.total_files as f32
+                    } else {
+                        0.0
+                    };
+
+                    ui.separator();
+                    ui.add(
+                        egui::ProgressBar::new(fraction)
+                            .desired_width(200.0)
+                            .text(format!(
+                                "Parsing vault: {}/{} files",
+                                progress.parsed_files, progress.total_files
+                            )),
+                    );
+                }
             });
         });
 
@@ -322,12 +544,24 @@ impl eframe::App for MyApp {
                     .default_open(true)
                     .show(ui, |ui| {
                         ui.horizontal(|ui| {
+                            // Errors are underlined as the query is typed
+                            let graphview = &self.graphview;
+                            let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                                let mut job = filter_layout_job(ui, text, graphview);
+                                job.wrap.max_width = wrap_width;
+                                ui.fonts(|fonts| fonts.layout_job(job))
+                            };
                             let response = ui.add_sized(
                                 [150.0, 20.0],
-                                egui::TextEdit::singleline(&mut self.filter_query),
+                                egui::TextEdit::singleline(&mut self.filter_query)
+                                    .layouter(&mut layouter),
                             );
                             ui.label("Filtering");
 
+                            if response.changed() {
+                                self.filtering_error =
+                                    self.graphview.parse_filter(&self.filter_query).err();
+                            }
                             if response.lost_focus()
                                 && ui.input(|i| i.key_pressed(egui::Key::Enter))
                             {
@@ -337,21 +571,11 @@ impl eframe::App for MyApp {
                         });
 
                         if let Some(parsing_error) = &self.filtering_error {
-                            let text = match parsing_error {
-                                ParsingError::InvalidExpression(expr) => {
-                                    "Invalid expression: ".to_owned() + expr
-                                }
-                                ParsingError::MissingOperand(expr) => {
-                                    "Missing operand: ".to_owned() + expr
-                                }
-                                ParsingError::MissingOperator(expr) => {
-                                    "Missing operator: ".to_owned() + expr
-                                }
-                                ParsingError::UnmatchedParentheses => {
-                                    "Unmatched parentheses".to_string()
-                                }
-                            };
-                            ui.label(text);
+                            ui.colored_label(
+                                ui.visuals().error_fg_color,
+                                parsing_error.to_string(),
+                            );
+                            ui.label(parsing_error.hint());
                         } else {
                             ui.label("");
                         }
@@ -363,10 +587,174 @@ impl eframe::App for MyApp {
                         }
                     });
 
-                egui::CollapsingHeader::new("Filter settings")
+                egui::CollapsingHeader::new("Edge settings")
                     .default_open(true)
                     .show(ui, |ui| {
-                        ui.label("...");
+                        let edge_filter = &mut self.edge_filter;
+                        let mut changed =
+                            ui.checkbox(&mut edge_filter.links, "Show links").changed();
+                        changed |= ui
+                            .checkbox(&mut edge_filter.embeds, "Show embeds")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut edge_filter.relations, "Show canvas relations")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut edge_filter.hierarchy, "Show hierarchy links")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut edge_filter.mentions, "Show unlinked mentions")
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut edge_filter.properties, "Show property links")
+                            .changed();
+
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add_sized(
+                                    [80.0, 20.0],
+                                    egui::DragValue::new(&mut edge_filter.min_count)
+                                        .speed(0.1)
+                                        .clamp_range(1..=50),
+                                )
+                                .changed();
+                            ui.label("Minimum link count");
+                        });
+
+                        if changed {
+                            self.graphview.set_edge_filter(edge_filter.clone());
+                        }
+                    });
+
+                egui::CollapsingHeader::new("Vault settings")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        egui::ComboBox::from_label("Vault format")
+                            .selected_text(self.scan_config.format.name())
+                            .show_ui(ui, |ui| {
+                                for format in VaultFormat::ALL {
+                                    ui.selectable_value(
+                                        &mut self.scan_config.format,
+                                        format,
+                                        format.name(),
+                                    );
+                                }
+                            });
+                        ui.checkbox(&mut self.scan_config.skip_hidden, "Skip hidden files");
+                        ui.checkbox(
+                            &mut self.scan_config.follow_symlinks,
+                            "Follow symbolic links",
+                        );
+                        ui.checkbox(
+                            &mut self.scan_config.use_obsidian_settings,
+                            "Use Obsidian excluded files",
+                        );
+                        ui.checkbox(
+                            &mut self.scan_config.include_attachments,
+                            "Show attachments",
+                        );
+                        ui.checkbox(
+                            &mut self.scan_config.include_canvas_edges,
+                            "Show canvas edges",
+                        );
+                        ui.checkbox(&mut self.scan_config.index_content, "Index note contents");
+                        ui.checkbox(
+                            &mut self.scan_config.include_unlinked_mentions,
+                            "Find unlinked mentions",
+                        );
+                        ui.checkbox(&mut self.scan_config.use_cache, "Cache parsed files");
+                        if ui
+                            .checkbox(&mut self.watch_vault, "Watch for changes")
+                            .changed()
+                        {
+                            self.update_watcher();
+                        }
+
+                        ui.label("Include patterns");
+                        ui.add_sized(
+                            [200.0, 40.0],
+                            egui::TextEdit::multiline(&mut self.include_patterns),
+                        );
+                        ui.label("Exclude patterns");
+                        ui.add_sized(
+                            [200.0, 60.0],
+                            egui::TextEdit::multiline(&mut self.exclude_patterns),
+                        );
+
+                        if ui.button("Reload vault").clicked() {
+                            let patterns = |text: &str| -> Vec<String> {
+                                text.lines()
+                                    .map(|line| line.trim().to_string())
+                                    .filter(|line| !line.is_empty())
+                                    .collect()
+                            };
+                            self.scan_config.include = patterns(&self.include_patterns);
+                            self.scan_config.exclude = patterns(&self.exclude_patterns);
+
+                            if let Some(vault_dir) = self.vault_dir.clone() {
+                                self.load_vault(&vault_dir)
+                            }
+                        }
+                    });
+
+                egui::CollapsingHeader::new("Unlinked mentions")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        let mentions = self.graphview.unlinked_mentions();
+                        if mentions.is_empty() {
+                            ui.label("No unlinked mentions (enable them in the vault settings)");
+                        }
+
+                        egui::ScrollArea::vertical()
+                            .id_source("unlinked_mentions")
+                            .max_height(250.0)
+                            .show(ui, |ui| {
+                                for (source, target, contexts) in mentions {
+                                    egui::CollapsingHeader::new(format!(
+                                        "{} → {} ({})",
+                                        self.graphview.node_title(source),
+                                        self.graphview.node_title(target),
+                                        contexts.len()
+                                    ))
+                                    .id_source((source, target))
+                                    .show(ui, |ui| {
+                                        for context in contexts {
+                                            ui.label(context);
+                                        }
+                                    });
+                                }
+                            });
+                    });
+
+                egui::CollapsingHeader::new("Parse report")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        if let Some(vault_error) = &self.vault_error {
+                            ui.colored_label(ui.visuals().error_fg_color, vault_error.to_string());
+                        }
+
+                        ui.label(format!(
+                            "{} files parsed, {} problems",
+                            self.parse_report.parsed_files,
+                            self.parse_report.diagnostics.len()
+                        ));
+
+                        egui::ScrollArea::vertical()
+                            .max_height(250.0)
+                            .show(ui, |ui| {
+                                for (category, diagnostics) in self.parse_report.categories() {
+                                    egui::CollapsingHeader::new(format!(
+                                        "{} ({})",
+                                        category,
+                                        diagnostics.len()
+                                    ))
+                                    .show(ui, |ui| {
+                                        for diagnostic in diagnostics {
+                                            ui.label(diagnostic.to_string());
+                                        }
+                                    });
+                                }
+                            });
                     });
             });
 
@@ -381,8 +769,116 @@ impl eframe::App for MyApp {
     }
 }
 
+// Lay out a filtering query, underlining the part a parsing error refers to
+fn filter_layout_job(ui: &egui::Ui, text: &str, graphview: &GraphView) -> egui::text::LayoutJob {
+    let format = egui::TextFormat {
+        font_id: egui::TextStyle::Body.resolve(ui.style()),
+        color: ui.visuals().widgets.inactive.text_color(),
+        ..Default::default()
+    };
+    let mut job = egui::text::LayoutJob::default();
+
+    match graphview.parse_filter(text).err().map(|error| error.span()) {
+        Some(span) if span.start < text.len() => {
+            let end = span.end.min(text.len());
+            job.append(&text[..span.start], 0.0, format.clone());
+            job.append(
+                &text[span.start..end],
+                0.0,
+                egui::TextFormat {
+                    color: ui.visuals().error_fg_color,
+                    underline: egui::Stroke::new(1.5, ui.visuals().error_fg_color),
+                    ..format.clone()
+                },
+            );
+            job.append(&text[end..], 0.0, format);
+        }
+        _ => job.append(text, 0.0, format),
+    }
+    job
+}
+
 // MyApp custom painting
 impl MyApp {
+    // Width of an edge, which grows with the number of links it stands for
+    fn edge_width(&self, edge_index: EdgeIndex) -> f32 {
+        self.link_width * self.graphview.edge(edge_index).map_or(1.0, Edge::strength)
+    }
+
+    // Draw the line of an edge, dashed for unlinked mentions (which are only potential links)
+    fn edge_line(
+        &self,
+        painter: &egui::Painter,
+        edge_index: EdgeIndex,
+        points: [egui::Pos2; 2],
+        stroke: egui::Stroke,
+    ) {
+        if self.graphview.edge_kind(edge_index) == Some(LinkKind::Mention) {
+            let dash_length = 4.0 * stroke.width.max(1.0);
+            painter.extend(egui::Shape::dashed_line(
+                &points,
+                stroke,
+                dash_length,
+                dash_length,
+            ));
+        } else {
+            painter.line_segment(points, stroke);
+        }
+    }
+
+    // Find the visible edge closest to a screen position, if it lies on the edge
+    fn edge_at(&self, pos: egui::Pos2) -> Option<EdgeIndex> {
+        self.graphview
+            .edge_start_end_positions()
+            .into_iter()
+            .filter_map(|(edge_index, start_pos, end_pos)| {
+                let start = (self.zoom * start_pos).to_pos2() + self.frame_center;
+                let end = (self.zoom * end_pos).to_pos2() + self.frame_center;
+
+                // Distance from the position to the closest point of the line segment
+                let segment = end - start;
+                let t = ((pos - start).dot(segment) / segment.length_sq().max(f32::EPSILON))
+                    .clamp(0.0, 1.0);
+                let distance = (start + t * segment).distance(pos);
+
+                (distance <= (self.edge_width(edge_index) / 2.0).max(4.0))
+                    .then_some((edge_index, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(edge_index, _)| edge_index)
+    }
+
+    // Show the pages of an edge, and the lines and sentences of its links, next to the pointer
+    fn edge_tooltip(&self, ctx: &egui::Context, edge_index: EdgeIndex) {
+        let (Some(edge), Some((source, target))) = (
+            self.graphview.edge(edge_index),
+            self.graphview.graph.edge_endpoints(edge_index),
+        ) else {
+            return;
+        };
+
+        egui::show_tooltip_at_pointer(ctx, egui::Id::new("edge_tooltip"), |ui| {
+            ui.strong(format!(
+                "{} → {}",
+                self.graphview.node_title(source),
+                self.graphview.node_title(target)
+            ));
+
+            let occurrences: Vec<(usize, &str)> = edge.occurrences().collect();
+            for (line, context) in occurrences.iter().take(MAX_EDGE_TOOLTIP_LINES) {
+                ui.label(format!("Line {}: {}", line, context));
+            }
+            if occurrences.len() > MAX_EDGE_TOOLTIP_LINES {
+                ui.label(format!(
+                    "… and {} more",
+                    occurrences.len() - MAX_EDGE_TOOLTIP_LINES
+                ));
+            } else if occurrences.is_empty() {
+                ui.label(format!("{} links", edge.count));
+            }
+        });
+    }
+
     // Graph view
     fn custom_painting(&mut self, ui: &mut egui::Ui) {
         // Allocate interactive graphing area and initiate a painter
@@ -413,14 +909,18 @@ impl MyApp {
         match self.draw_arrows {
             true => {
                 // Draw arrows
-                for (_, start_pos, end_pos) in self.graphview.edge_start_end_positions() {
+                for (edge_index, start_pos, end_pos) in self.graphview.edge_start_end_positions() {
                     let dir = (end_pos - start_pos).normalized();
 
                     let origin = (self.zoom * start_pos).to_pos2() + self.frame_center;
                     let tip = origin + self.zoom * (end_pos - start_pos - self.node_size * dir);
 
-                    let stroke =
-                        egui::Stroke::new(self.link_width, egui::Color32::from_rgb(155, 155, 155));
+                    let color = if Some(edge_index) == self.hovering_edge {
+                        egui::Color32::from_rgb(255, 105, 105)
+                    } else {
+                        egui::Color32::from_rgb(155, 155, 155)
+                    };
+                    let stroke = egui::Stroke::new(self.edge_width(edge_index), color);
                     let angle = egui::emath::Rot2::from_angle(std::f32::consts::TAU / 10.0);
 
                     let tip_length = match self.zoom * (end_pos - start_pos).length() {
@@ -430,7 +930,7 @@ impl MyApp {
                         _ => self.arrow_size,
                     };
 
-                    painter.line_segment([origin, tip], stroke);
+                    self.edge_line(&painter, edge_index, [origin, tip], stroke);
                     painter.line_segment([tip, tip - tip_length * (angle * dir)], stroke);
                     painter.line_segment([tip, tip - tip_length * (angle.inverse() * dir)], stroke);
                 }
@@ -447,27 +947,49 @@ impl MyApp {
                         // Check if edge is connected to hovering node
                         if Some(edge_start_node) == self.hovering_node
                             || Some(edge_end_node) == self.hovering_node
+                            || Some(edge_index) == self.hovering_edge
                         {
-                            painter.line_segment(
+                            self.edge_line(
+                                &painter,
+                                edge_index,
                                 [
                                     (self.zoom * start_pos).to_pos2() + self.frame_center,
                                     (self.zoom * end_pos).to_pos2() + self.frame_center,
                                 ],
                                 egui::Stroke::new(
-                                    self.link_width,
+                                    self.edge_width(edge_index),
                                     egui::Color32::from_rgb(255, 105, 105),
                                 ),
-                            )
+                            );
+
+                            // Links from property values are labelled with the property name
+                            let labels = self.graphview.edge_labels(edge_index);
+                            if !labels.is_empty() {
+                                painter.text(
+                                    (self.zoom * (start_pos + end_pos) / 2.0).to_pos2()
+                                        + self.frame_center,
+                                    egui::Align2::CENTER_CENTER,
+                                    labels.join(", "),
+                                    egui::FontId::proportional(self.text_size),
+                                    egui::Color32::from_rgb(255, 105, 105),
+                                );
+                            }
                         } else {
-                            painter.line_segment(
+                            // Canvas relations are drawn in a different color than links
+                            let color = match self.graphview.edge_kind(edge_index) {
+                                Some(LinkKind::Relation) => egui::Color32::from_rgb(105, 155, 255),
+                                Some(LinkKind::Hierarchy) => egui::Color32::from_rgb(120, 200, 140),
+                                _ => egui::Color32::from_rgb(155, 155, 155),
+                            };
+
+                            self.edge_line(
+                                &painter,
+                                edge_index,
                                 [
                                     (self.zoom * start_pos).to_pos2() + self.frame_center,
                                     (self.zoom * end_pos).to_pos2() + self.frame_center,
                                 ],
-                                egui::Stroke::new(
-                                    self.link_width,
-                                    egui::Color32::from_rgb(155, 155, 155),
-                                ),
+                                egui::Stroke::new(self.edge_width(edge_index), color),
                             )
                         }
                     }
@@ -478,28 +1000,27 @@ impl MyApp {
         // Draw nodes
         for (node_index, node_pos) in self.graphview.node_positions() {
             if self.graphview.node_is_visible(node_index) {
-                // Check if node is being hovered
-                if Some(node_index) != self.hovering_node {
-                    // Check if node is not empty
-                    if !self.graphview.node_is_empty(node_index) {
-                        painter.circle_filled(
-                            (self.zoom * node_pos).to_pos2() + self.frame_center,
-                            self.zoom * self.node_size,
-                            egui::Color32::from_rgb(200, 200, 200),
-                        )
-                    } else {
-                        painter.circle_filled(
-                            (self.zoom * node_pos).to_pos2() + self.frame_center,
-                            self.zoom * self.node_size,
-                            egui::Color32::from_rgb(50, 50, 50),
-                        )
-                    }
+                let center = (self.zoom * node_pos).to_pos2() + self.frame_center;
+                let radius = self.zoom * self.node_size;
+
+                // Hovered nodes are highlighted, empty nodes are darker
+                let color = if Some(node_index) == self.hovering_node {
+                    egui::Color32::from_rgb(255, 105, 105)
+                } else if !self.graphview.node_is_empty(node_index) {
+                    egui::Color32::from_rgb(200, 200, 200)
                 } else {
-                    painter.circle_filled(
-                        (self.zoom * node_pos).to_pos2() + self.frame_center,
-                        self.zoom * self.node_size,
-                        egui::Color32::from_rgb(255, 105, 105),
+                    egui::Color32::from_rgb(50, 50, 50)
+                };
+
+                // Attachments are drawn as squares, notes as circles
+                if self.graphview.node_is_attachment(node_index) {
+                    painter.rect_filled(
+                        egui::Rect::from_center_size(center, egui::Vec2::splat(1.8 * radius)),
+                        0.0,
+                        color,
                     )
+                } else {
+                    painter.circle_filled(center, radius, color)
                 }
             }
         }
@@ -580,7 +1101,7 @@ impl MyApp {
                     }
 
                     // Check if still no node is being dragged, then drag screen
-                    if self.dragging_node == None {
+                    if self.dragging_node.is_none() {
                         self.frame_center += response.drag_delta()
                     }
                 }
@@ -592,7 +1113,7 @@ impl MyApp {
         // Hover over node
         let old_node_hover_time = self.node_hover_time;
 
-        if response.hovered() && self.dragging_node == None {
+        if response.hovered() && self.dragging_node.is_none() {
             for (index, node_pos) in self.graphview.node_positions() {
                 if ((self.zoom * node_pos) + self.frame_center - mouse_pos.to_vec2()).length()
                     <= self.zoom * self.node_size
@@ -614,6 +1135,15 @@ impl MyApp {
             self.hovering_node = None;
         }
 
+        // Hover over edge, showing where its links appear
+        self.hovering_edge = None;
+        if response.hovered() && self.dragging_node.is_none() && self.hovering_node.is_none() {
+            self.hovering_edge = self.edge_at(mouse_pos);
+        }
+        if let Some(edge_index) = self.hovering_edge {
+            self.edge_tooltip(ui.ctx(), edge_index);
+        }
+
         /*
         if let Some(position) = response.interact_pointer_pos() {
             println!("{:?}", position);
@@ -660,8 +1190,8 @@ impl MyApp {
 
             if dropped_file != egui::DroppedFile::default() {
                 if let Some(path) = &dropped_file.path {
-                    if path.is_dir() {
-                        self.graphview = GraphView::new(vault_to_graph(path))
+                    if path.is_dir() || roam::is_roam_export(path) {
+                        self.load_vault(path)
                     }
                 };
             };
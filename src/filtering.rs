@@ -1,91 +1,275 @@
 //! This module helps with evaluating filtering expressions
-//! 
-//! - `(A & tag:#B) | (C & -tag:#D)` -> expression (describes boolean logic operations)
-//! - `tag:#B` -> filter (describes specific field which is filtered)
-//! - `B` -> query (regex which returns true / false)
+//!
+//! - `(A & tag:#B) | (C -tag:#D)` -> expression (describes boolean logic operations)
+//! - `tag:#B` -> filter (describes specific field which is filtered, see [Field])
+//! - `B` -> query (text, `=exact` text, glob or `/regex/` which returns true / false)
+//! - `priority>=2`, `[status:done]`, `has:aliases` -> comparison of a property or statistic
+//!   with a typed value, checked against the types of the properties in the vault
+//!   (see [comparison])
+//!
+//! Expressions are split into tokens by the [lexer] and parsed into a [BooleanExpr] by the [parser].
 
-use crate::Page;
+use std::{fmt, ops::Range};
+
+use crate::{
+    vault_parser::{links::normalize_path, tags::tag_matches},
+    Heading, Page, PageKind,
+};
+
+use comparison::{has_property, Comparison, PropertyTypes};
+use lexer::tokenize;
+use matcher::Matcher;
+use parser::parse_tokens;
+
+pub mod comparison;
+pub mod lexer;
+pub mod matcher;
+pub mod parser;
 
 /// Defines the boolean expression tree data structure
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BooleanExpr {
     Not(Box<BooleanExpr>),
     And(Box<BooleanExpr>, Box<BooleanExpr>),
     Or(Box<BooleanExpr>, Box<BooleanExpr>),
-    Filter(String),
+    Filter(Filter),
+    /// Comparison of a property or statistic with a value (`priority>=2`)
+    Compare(Comparison),
+    /// Presence of a property (`has:aliases`)
+    Has(String),
 }
 
-/// Describes which parsing error occured in [parse_boolean_expr]
-#[derive(Debug)]
-pub enum ParsingError {
-    UnmatchedParentheses,
-    MissingOperand(String),
-    MissingOperator(String),
-    InvalidExpression(String),
+/// Describes which value of a page a [Filter] is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Title of the page (`title:` or no field)
+    Title,
+    /// Any tag of the page (`tag:#`)
+    Tag,
+    /// Kind of the page, or the file type of an attachment (`kind:`)
+    Kind,
+    /// Vault-relative path of the page, with extension (`path:`)
+    Path,
+    /// Vault-relative folder of the page (`folder:`)
+    Folder,
+    /// File name of the page, with extension (`file:`)
+    File,
+    /// Extension of the page's file (`ext:`)
+    Extension,
+    /// Text of a note (`content:`)
+    Content,
+    /// Any line of a note (`line:`)
+    Line,
+    /// Any section of a note, from a heading up to the next one (`section:`)
+    Section,
 }
 
-// Helper function to check if a character is a valid query character
-fn is_valid_char(c: char) -> bool {
-    c != '(' && c != ')' && c != '&' && c != '|' && c != '-' && c != ' '
+impl Field {
+    /// All fields, in the order they are tried when parsing a filter
+    pub const ALL: [Field; 10] = [
+        Field::Title,
+        Field::Tag,
+        Field::Kind,
+        Field::Path,
+        Field::Folder,
+        Field::File,
+        Field::Extension,
+        Field::Content,
+        Field::Line,
+        Field::Section,
+    ];
+
+    /// Returns the prefix a filter on the field starts with
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Field::Title => "title:",
+            Field::Tag => "tag:#",
+            Field::Kind => "kind:",
+            Field::Path => "path:",
+            Field::Folder => "folder:",
+            Field::File => "file:",
+            Field::Extension => "ext:",
+            Field::Content => "content:",
+            Field::Line => "line:",
+            Field::Section => "section:",
+        }
+    }
+
+    /// Returns whether the field is matched against the text of notes, which is only kept
+    /// if it is indexed
+    pub fn is_full_text(self) -> bool {
+        matches!(self, Field::Content | Field::Line | Field::Section)
+    }
 }
 
-/// Turns a string into a boolean syntax tree (recursively)
-/// - `&` = AND operator
-/// - `|` = OR operator
-/// - `-` = NOT operator
-pub fn parse_boolean_expr(expr: &str) -> Result<BooleanExpr, ParsingError> {
-    let expr = expr.trim();
+/// This struct describes a filter (`field:query`), with its query parsed into a [Matcher]
+#[derive(Debug, Clone)]
+pub struct Filter {
+    /// Filter as written, without quotes and escapes
+    pub text: String,
+    pub field: Field,
+    pub matcher: Matcher,
+}
+
+impl Filter {
+    /// Parses a filter, reporting invalid queries (such as regular expressions) with a message
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (field, query) = Field::ALL
+            .iter()
+            .find_map(|field| Some((*field, text.strip_prefix(field.prefix())?)))
+            .unwrap_or((Field::Title, text));
 
-    // Check for the base case (single variable)
-    if expr.chars().all(is_valid_char) {
-        return Ok(BooleanExpr::Filter(expr.to_string()));
+        Ok(Self {
+            text: text.to_string(),
+            field,
+            matcher: Matcher::parse(query)?,
+        })
     }
+}
 
-    // Check for NOT operator
-    if expr.starts_with('-') {
-        let inner_expr = parse_boolean_expr(&expr[1..])?;
-        return Ok(BooleanExpr::Not(Box::new(inner_expr)));
+// Filters are compared by their text, as compiled regular expressions cannot be compared
+impl PartialEq for Filter {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
     }
+}
 
-    // Find the index where the AND or OR operator is located
-    let mut idx = expr.len() - 1;
-    let mut level = 0;
-
-    while idx > 0 {
-        let c = expr.chars().nth(idx).unwrap();
-        match c {
-            '(' => level -= 1,
-            ')' => level += 1,
-            '&' if level == 0 => break,
-            '|' if level == 0 => break,
-            _ => {}
+/// Describes which parsing error occured in [parse_boolean_expr], and where.
+///
+/// Every error holds the byte range of the expression it refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsingError {
+    /// A `(` is never closed (range of the `(`)
+    UnclosedParenthesis(Range<usize>),
+    /// A `)` was never opened (range of the `)`)
+    UnopenedParenthesis(Range<usize>),
+    /// An operator (`&`, `|`, `-` or `(`) lacks an operand (range of the operator)
+    MissingOperand(String, Range<usize>),
+    /// A `"` is never closed (range from the `"` to the end of the expression)
+    UnclosedQuote(Range<usize>),
+    /// The query of a filter is invalid, such as a malformed regex (range of the filter)
+    InvalidQuery(String, Range<usize>),
+    /// A comparison is malformed or compares values of different types (range of the comparison)
+    InvalidComparison(String, Range<usize>),
+    /// A filter starts with an unknown field which resembles a known one (range of the field)
+    UnknownField {
+        field: String,
+        suggestion: String,
+        span: Range<usize>,
+    },
+    /// A full-text filter is used, but the text of notes is not indexed (range of the filter)
+    NotIndexed(Range<usize>),
+}
+
+impl ParsingError {
+    /// Returns the byte range of the expression the error refers to
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            ParsingError::UnclosedParenthesis(span)
+            | ParsingError::UnopenedParenthesis(span)
+            | ParsingError::MissingOperand(_, span)
+            | ParsingError::UnclosedQuote(span)
+            | ParsingError::InvalidQuery(_, span)
+            | ParsingError::InvalidComparison(_, span)
+            | ParsingError::UnknownField { span, .. }
+            | ParsingError::NotIndexed(span) => span.clone(),
         }
-        idx -= 1;
     }
 
-    if idx == 0 {
-        // The expression is surrounded by parentheses, so we ignore them and parse the inner part
-        if expr.starts_with('(') && expr.ends_with(')') {
-            return parse_boolean_expr(&expr[1..expr.len() - 1]);
+    /// Returns a hint on how to fix the expression
+    pub fn hint(&self) -> String {
+        match self {
+            ParsingError::UnclosedParenthesis(_) => {
+                "unclosed `(` opened here, add a matching `)`".to_string()
+            }
+            ParsingError::UnopenedParenthesis(_) => {
+                "remove the `)`, or add a matching `(` before it".to_string()
+            }
+            ParsingError::MissingOperand(operator, _) if operator == "(" => {
+                "add a filter within the parentheses".to_string()
+            }
+            ParsingError::MissingOperand(operator, _) if operator == "-" => {
+                "add a filter after `-`, or quote text starting with `-`".to_string()
+            }
+            ParsingError::MissingOperand(operator, _) => {
+                format!("add a filter on both sides of `{}`", operator)
+            }
+            ParsingError::UnclosedQuote(_) => {
+                "add a closing `\"`, or escape the quote as `\\\"`".to_string()
+            }
+            ParsingError::InvalidQuery(..) => {
+                "quote regular expressions containing `|`, `(`, `)` or spaces".to_string()
+            }
+            ParsingError::InvalidComparison(..) => {
+                "compare numbers (`words>500`) or dates (`due<2026-11-01`) with `<` or `>`, \
+                 and text with `=` or `!=`"
+                    .to_string()
+            }
+            ParsingError::UnknownField { suggestion, .. } => {
+                format!("did you mean `{}`?", suggestion)
+            }
+            ParsingError::NotIndexed(_) => {
+                "enable \"Index note contents\" and reload the vault".to_string()
+            }
         }
-        // Invalid expression
-        if level != 0 {
-            return Err(ParsingError::UnmatchedParentheses);
-        } else if expr.chars().all(is_valid_char) {
-            return Err(ParsingError::MissingOperator(expr.to_string()));
-        } else {
-            return Err(ParsingError::InvalidExpression(expr.to_string()));
+    }
+}
+
+impl fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsingError::UnclosedParenthesis(_) | ParsingError::UnopenedParenthesis(_) => {
+                write!(f, "Unmatched parentheses")
+            }
+            ParsingError::MissingOperand(operator, _) => {
+                write!(f, "Missing operand for `{}`", operator)
+            }
+            ParsingError::UnclosedQuote(_) => write!(f, "Unclosed quote"),
+            ParsingError::InvalidQuery(message, _) => write!(f, "Invalid query: {}", message),
+            ParsingError::InvalidComparison(message, _) => {
+                write!(f, "Invalid comparison: {}", message)
+            }
+            ParsingError::UnknownField { field, .. } => write!(f, "Unknown field `{}`", field),
+            ParsingError::NotIndexed(_) => write!(f, "Content is not indexed"),
         }
     }
+}
+
+impl std::error::Error for ParsingError {}
 
-    let left_expr = parse_boolean_expr(&expr[..idx])?;
-    let right_expr = parse_boolean_expr(&expr[idx + 1..])?;
+/// Turns a string into a boolean syntax tree
+/// - `&` = AND operator (may be left out between operands)
+/// - `|` = OR operator
+/// - `-` = NOT operator (at the start of an operand)
+/// - `"..."` = quoted text, which may contain spaces and operators
+/// - `\` = escapes a following operator, quote, space or backslash
+///
+/// NOT binds strongest, then AND, then OR. An empty expression matches every page.
+///
+/// Only statistics can be compared, see [parse_boolean_expr_with] to compare properties.
+pub fn parse_boolean_expr(expr: &str) -> Result<BooleanExpr, ParsingError> {
+    parse_boolean_expr_with(expr, &PropertyTypes::default(), true)
+}
 
-    match expr.chars().nth(idx).unwrap() {
-        '&' => Ok(BooleanExpr::And(Box::new(left_expr), Box::new(right_expr))),
-        '|' => Ok(BooleanExpr::Or(Box::new(left_expr), Box::new(right_expr))),
-        _ => Err(ParsingError::InvalidExpression("Bruh2".to_string())),
+/// Turns a string into a boolean syntax tree like [parse_boolean_expr], comparing the
+/// properties in `property_types` and reporting values of other types than theirs.
+///
+/// Full-text filters (`content:`, `line:`, `section:`) are reported unless `content_indexed`.
+pub fn parse_boolean_expr_with(
+    expr: &str,
+    property_types: &PropertyTypes,
+    content_indexed: bool,
+) -> Result<BooleanExpr, ParsingError> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Ok(BooleanExpr::Filter(Filter {
+            text: String::new(),
+            field: Field::Title,
+            matcher: Matcher::parse("").unwrap(),
+        }));
     }
+
+    parse_tokens(tokens, property_types, content_indexed)
 }
 
 /// Evaluates a (nested) boolean expression for some input [Page]
@@ -100,26 +284,175 @@ pub fn evaluate_expr(expr: &BooleanExpr, input: &Page) -> bool {
             evaluate_expr(inner_expr_left.as_ref(), input)
                 || evaluate_expr(inner_expr_right.as_ref(), input)
         }
-        BooleanExpr::Filter(filter_string) => evaluate_filter(filter_string, input),
+        BooleanExpr::Filter(filter) => evaluate_filter(filter, input),
+        BooleanExpr::Compare(comparison) => comparison.evaluate(input),
+        BooleanExpr::Has(key) => has_property(key, input),
     }
 }
 
+/// Checks the field of a filter (`field:query`), returning the known field it most likely
+/// misspells, if it is unknown.
+///
+/// Fields which resemble no known field are taken as part of a title (`Chapter:Intro`).
+pub fn misspelled_field(filter: &str) -> Option<(&str, &'static str)> {
+    let (name, query) = filter.split_once(':')?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let field = &filter[..name.len() + 1];
+
+    let prefixes = Field::ALL.map(Field::prefix);
+    if prefixes.iter().any(|known| known.starts_with(field)) {
+        // Tags are always written with `#`
+        return (field == "tag:" && !query.starts_with('#')).then_some((field, "tag:#"));
+    }
+    prefixes
+        .into_iter()
+        .map(|known| {
+            let known_name = known.split(':').next().unwrap_or_default();
+            (edit_distance(&name.to_lowercase(), known_name), known)
+        })
+        .filter(|(distance, _)| *distance <= name.len().min(4) / 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| (field, known))
+}
+
+// Count the single-character insertions, deletions and substitutions between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
 /// Evaluates a filter for some input [Page]
-pub fn evaluate_filter(filter: &str, input: &Page) -> bool {
-    // Page filter
-    if filter.starts_with("title:") {
-        let query = filter.strip_prefix("title:").unwrap();
-        input.title.contains(query)
-
-    // Tag filter
-    } else if filter.starts_with("tag:#") {
-        let query = filter.strip_prefix("tag:#").unwrap();
-
-        input.tags.iter().any(|tag| tag.contains(query))
-
-    // Page filter
-    } else {
-        let query = filter;
-        input.title.contains(query)
+///
+/// Plain tag queries take tag hierarchies into account, and plain kind and extension queries
+/// match exactly (ignoring case). Full-text fields match nothing if the page has no content.
+pub fn evaluate_filter(filter: &Filter, input: &Page) -> bool {
+    let matcher = &filter.matcher;
+    let path = || normalize_path(&input.path);
+
+    match filter.field {
+        Field::Title => matcher.matches(&input.title),
+        Field::Tag => input.tags.iter().any(|tag| match matcher.plain_text() {
+            Some(query) => tag_matches(tag, query),
+            None => matcher.matches(tag),
+        }),
+        Field::Kind => {
+            let (kind, file_type) = match &input.kind {
+                PageKind::Note => ("note", None),
+                PageKind::Attachment { file_type, .. } => ("attachment", Some(file_type)),
+                PageKind::Canvas => ("canvas", None),
+                PageKind::Url => ("url", None),
+            };
+            match matcher.plain_text() {
+                Some(query) => {
+                    query == kind || file_type.is_some_and(|ext| ext.eq_ignore_ascii_case(query))
+                }
+                None => matcher.matches(kind) || file_type.is_some_and(|ext| matcher.matches(ext)),
+            }
+        }
+        Field::Path => !input.path.as_os_str().is_empty() && matcher.matches(&path()),
+        Field::Folder => {
+            let path = path();
+            let folder = path.rsplit_once('/').map_or("", |(folder, _)| folder);
+            !path.is_empty() && matcher.matches(folder)
+        }
+        Field::File => input
+            .path
+            .file_name()
+            .is_some_and(|name| matcher.matches(&name.to_string_lossy())),
+        Field::Extension => input.path.extension().is_some_and(|extension| {
+            let extension = extension.to_string_lossy();
+            match matcher.plain_text() {
+                Some(query) => extension.eq_ignore_ascii_case(query.trim_start_matches('.')),
+                None => matcher.matches(&extension),
+            }
+        }),
+        Field::Content => input
+            .content
+            .as_ref()
+            .is_some_and(|content| matcher.matches(content)),
+        Field::Line => input
+            .content
+            .as_ref()
+            .is_some_and(|content| content.lines().any(|line| matcher.matches(line))),
+        Field::Section => input.content.as_ref().is_some_and(|content| {
+            sections(content, &input.stats.headings)
+                .iter()
+                .any(|section| matcher.matches(section))
+        }),
+    }
+}
+
+// Split the text of a note into the part before the first heading and the sections starting
+// at each heading
+fn sections<'a>(content: &'a str, headings: &[Heading]) -> Vec<&'a str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut line_start = 0;
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        if headings.iter().any(|heading| heading.line == index + 1) && line_start > start {
+            sections.push(&content[start..line_start]);
+            start = line_start;
+        }
+        line_start += line.len();
+    }
+    sections.push(&content[start..]);
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PageStats;
+    use std::path::PathBuf;
+
+    #[test]
+    fn page_fields() {
+        let page = Page {
+            title: "Plan".to_string(),
+            path: PathBuf::from("projects/2023/Plan.md"),
+            stats: PageStats {
+                headings: vec![Heading {
+                    level: 2,
+                    text: "Tasks".to_string(),
+                    line: 3,
+                }],
+                ..Default::default()
+            },
+            content: Some("Intro with a goal\n\n## Tasks\nWrite the report\n".to_string()),
+            ..Default::default()
+        };
+        let matches = |filter: &str| evaluate_expr(&parse_boolean_expr(filter).unwrap(), &page);
+
+        assert!(matches(
+            "path:projects/2023 folder:=projects/2023 file:=Plan.md ext:.MD"
+        ));
+        assert!(!matches("folder:=projects") && !matches("ext:png"));
+        assert!(matches(
+            "content:\"goal\" line:/^Write/ section:\"/Tasks\\s+Write/\""
+        ));
+        assert!(!matches("line:\"goal Write\"") && !matches("section:\"/goal[^#]*Write/\""));
+
+        // Full-text filters are reported if the text of notes is not indexed
+        assert_eq!(
+            parse_boolean_expr_with("a -line:goal", &PropertyTypes::default(), false),
+            Err(ParsingError::NotIndexed(3..12))
+        );
+        assert!(
+            parse_boolean_expr_with("title:content:a", &PropertyTypes::default(), false).is_ok()
+        );
     }
 }
@@ -0,0 +1,492 @@
+//! This module compares the properties and statistics of pages with typed values
+//!
+//! - `[status:done]` matches pages whose `status` property equals `done`, `[status]` pages
+//!   which have a `status` property, as does `has:status`
+//! - `priority>=2`, `due<2026-11-01`, `words>500` compare with `=`, `!=`, `<`, `<=`, `>` or `>=`
+//! - Values are read as a boolean, a number, a date (`YYYY-MM-DD`) or as text, in that order.
+//!   Text and booleans can only be compared with `=` and `!=`.
+//! - List properties match if any of their items matches (all of them for `!=`)
+//!
+//! The statistics `words`, `characters`, `size`, `headings`, `open_tasks`, `completed_tasks`,
+//! `created` and `modified` take precedence over properties of the same name.
+//!
+//! Terms are only read as comparisons if they compare a statistic or a property found in the
+//! vault ([PropertyTypes]), so that other terms (`a=b`, `x<y`) remain title filters. Keys may
+//! contain any characters but operators, and are quoted if they contain spaces
+//! (`"due date"<2026-11-01`). Values are read as the types the key holds in the vault, and
+//! values of other types are reported as errors.
+
+use chrono::{DateTime, Local, NaiveDate};
+use regex::Regex;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    sync::LazyLock,
+};
+
+use crate::{filtering::BooleanExpr, vault_parser::frontmatter::parse_date, Page, PropertyValue};
+
+static COMPARISON_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([^=<>!\[\]]+?)(>=|<=|!=|=|>|<)(.*)$").unwrap());
+static PROPERTY_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[([^\[\]:]+)(?::(.*))?\]$").unwrap());
+
+/// Describes how a [Comparison] relates a page value to its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    /// Returns whether the operator orders values, rather than checking their equality
+    pub fn is_ordering(self) -> bool {
+        !matches!(self, Operator::Equal | Operator::NotEqual)
+    }
+
+    // Check whether the ordering of a page value and the compared value satisfies the operator
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Equal => ordering.is_eq(),
+            Operator::NotEqual => ordering.is_ne(),
+            Operator::Less => ordering.is_lt(),
+            Operator::LessOrEqual => ordering.is_le(),
+            Operator::Greater => ordering.is_gt(),
+            Operator::GreaterOrEqual => ordering.is_ge(),
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Operator::Equal => "=",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Describes the type of a [Value]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValueType {
+    Boolean,
+    Number,
+    Date,
+    Text,
+}
+
+impl ValueType {
+    /// Returns the name of the type, as used in error messages
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueType::Boolean => "boolean",
+            ValueType::Number => "number",
+            ValueType::Date => "date",
+            ValueType::Text => "text",
+        }
+    }
+}
+
+/// This enum stores a typed value to compare page values with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Boolean(bool),
+    Number(f64),
+    Date(NaiveDate),
+    Text(String),
+}
+
+impl Value {
+    /// Reads a value as a boolean, a number, a date or as text
+    pub fn parse(text: &str) -> Self {
+        match text {
+            "true" => return Value::Boolean(true),
+            "false" => return Value::Boolean(false),
+            _ => {}
+        }
+        if let Some(number) = text.parse::<f64>().ok().filter(|number| number.is_finite()) {
+            return Value::Number(number);
+        }
+        match parse_date(text) {
+            Some(PropertyValue::Date(date)) => Value::Date(date),
+            Some(PropertyValue::DateTime(date_time)) => Value::Date(date_time.date()),
+            _ => Value::Text(text.to_string()),
+        }
+    }
+
+    /// Converts a property value, with links compared by their target
+    pub fn from_property(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Text(text) => Some(Value::Text(text.clone())),
+            PropertyValue::Number(number) => Some(Value::Number(*number)),
+            PropertyValue::Boolean(boolean) => Some(Value::Boolean(*boolean)),
+            PropertyValue::Date(date) => Some(Value::Date(*date)),
+            PropertyValue::DateTime(date_time) => Some(Value::Date(date_time.date())),
+            PropertyValue::Link(inner) => {
+                let target = inner.split(['|', '#']).next().unwrap_or_default();
+                Some(Value::Text(target.trim().to_string()))
+            }
+            PropertyValue::List(_) => None,
+        }
+    }
+
+    /// Returns the type of the value
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::Boolean(_) => ValueType::Boolean,
+            Value::Number(_) => ValueType::Number,
+            Value::Date(_) => ValueType::Date,
+            Value::Text(_) => ValueType::Text,
+        }
+    }
+
+    // Order two values of the same type (text case-insensitively)
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+            (Value::Text(a), Value::Text(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+            _ => None,
+        }
+    }
+}
+
+/// This struct describes the comparison of a property or statistic of a page with a value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Name of the property or statistic
+    pub key: String,
+    pub operator: Operator,
+    pub value: Value,
+}
+
+impl Comparison {
+    /// Checks whether the property or statistic of a page satisfies the comparison.
+    ///
+    /// Values of another type than the compared value only satisfy `!=`.
+    pub fn evaluate(&self, page: &Page) -> bool {
+        if let Some(value) = statistic(&self.key, page) {
+            return self.holds(&value);
+        }
+
+        page.properties
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&self.key))
+            .is_some_and(|(_, value)| self.holds_for_property(value))
+    }
+
+    // Check the comparison for a property value, or for the items of a list
+    fn holds_for_property(&self, value: &PropertyValue) -> bool {
+        match value {
+            PropertyValue::List(items) if self.operator == Operator::NotEqual => {
+                items.iter().all(|item| self.holds_for_property(item))
+            }
+            PropertyValue::List(items) => items.iter().any(|item| self.holds_for_property(item)),
+            value => Value::from_property(value).is_some_and(|value| self.holds(&value)),
+        }
+    }
+
+    fn holds(&self, value: &Value) -> bool {
+        match value.compare(&self.value) {
+            Some(ordering) => self.operator.holds(ordering),
+            None => self.operator == Operator::NotEqual,
+        }
+    }
+}
+
+/// This struct stores the types of the values of every property in a vault, so that
+/// comparisons can be checked when they are parsed.
+///
+/// Properties are looked up case-insensitively, and lists count with the types of their items.
+#[derive(Debug, Clone, Default)]
+pub struct PropertyTypes {
+    types: HashMap<String, BTreeSet<ValueType>>,
+}
+
+impl PropertyTypes {
+    /// Collects the types of the properties of some pages
+    pub fn new<'a>(pages: impl IntoIterator<Item = &'a Page>) -> Self {
+        let mut property_types = Self::default();
+        for page in pages {
+            for (key, value) in &page.properties {
+                let types = property_types.types.entry(key.to_lowercase()).or_default();
+                add_types(value, types);
+            }
+        }
+        property_types
+    }
+
+    /// Returns the types of the values of a statistic or property, if it is known
+    pub fn get(&self, key: &str) -> Option<BTreeSet<ValueType>> {
+        match statistic_type(key) {
+            Some(value_type) => Some(BTreeSet::from([value_type])),
+            None => self.types.get(&key.to_lowercase()).cloned(),
+        }
+    }
+}
+
+// Add the type of a property value, or the types of the items of a list
+fn add_types(value: &PropertyValue, types: &mut BTreeSet<ValueType>) {
+    match value {
+        PropertyValue::List(items) => items.iter().for_each(|item| add_types(item, types)),
+        value => types.extend(Value::from_property(value).map(|value| value.value_type())),
+    }
+}
+
+/// Checks whether a page has a property (case-insensitively)
+pub fn has_property(key: &str, page: &Page) -> bool {
+    page.properties
+        .keys()
+        .any(|property| property.eq_ignore_ascii_case(key))
+}
+
+/// Parses a filtering term into a comparison (`priority>=2`, `[status:done]`) or a property
+/// check (`has:aliases`, `[aliases]`).
+///
+/// Returns `None` if the term is neither, or refers to a key which is neither a statistic
+/// nor in `property_types`. Returns a message if the comparison is invalid, such as if the
+/// value does not have a type of the key.
+pub fn parse_comparison(
+    term: &str,
+    property_types: &PropertyTypes,
+) -> Option<Result<BooleanExpr, String>> {
+    if let Some(key) = term.strip_prefix("has:") {
+        return Some(match key.trim() {
+            "" => Err("`has:` needs the name of a property".to_string()),
+            key => Ok(BooleanExpr::Has(key.to_string())),
+        });
+    }
+
+    let (key, operator, value) = if let Some(captures) = PROPERTY_PATTERN.captures(term) {
+        let key = captures.get(1).unwrap().as_str().trim();
+        match captures.get(2) {
+            None => {
+                property_types.get(key)?;
+                return Some(Ok(BooleanExpr::Has(key.to_string())));
+            }
+            Some(value) => (key, Operator::Equal, value.as_str()),
+        }
+    } else {
+        let captures = COMPARISON_PATTERN.captures(term)?;
+        let operator = match &captures[2] {
+            "=" => Operator::Equal,
+            "!=" => Operator::NotEqual,
+            "<" => Operator::Less,
+            "<=" => Operator::LessOrEqual,
+            ">" => Operator::Greater,
+            _ => Operator::GreaterOrEqual,
+        };
+        (
+            captures.get(1).unwrap().as_str().trim(),
+            operator,
+            captures.get(3).unwrap().as_str(),
+        )
+    };
+    let types = property_types.get(key)?;
+
+    let value = value.trim();
+    if value.is_empty() {
+        return Some(Err(format!("`{}` is not compared with a value", key)));
+    }
+    let value = match typed_value(value, &types) {
+        Ok(value) => value,
+        Err(value_type) => {
+            let names: Vec<&str> = types.iter().map(|value_type| value_type.name()).collect();
+            return Some(Err(format!(
+                "`{}` is a {}, not a {}",
+                key,
+                names.join(" or "),
+                value_type.name()
+            )));
+        }
+    };
+    if operator.is_ordering() && matches!(value, Value::Text(_) | Value::Boolean(_)) {
+        return Some(Err(format!(
+            "`{}` compares numbers and dates, not a {}",
+            operator,
+            value.value_type().name()
+        )));
+    }
+
+    Some(Ok(BooleanExpr::Compare(Comparison {
+        key: key.to_string(),
+        operator,
+        value,
+    })))
+}
+
+// Read a value as one of the types of a key, falling back to text if the key holds text
+// (so that `[code:123]` matches the text `123`). Returns the type the value was read as
+// if the key holds none of it.
+fn typed_value(text: &str, types: &BTreeSet<ValueType>) -> Result<Value, ValueType> {
+    let value = Value::parse(text);
+    if types.is_empty() || types.contains(&value.value_type()) {
+        Ok(value)
+    } else if types.contains(&ValueType::Text) {
+        Ok(Value::Text(text.to_string()))
+    } else {
+        Err(value.value_type())
+    }
+}
+
+// Type of the statistic a key refers to, if any
+fn statistic_type(key: &str) -> Option<ValueType> {
+    match key {
+        "words" | "characters" | "size" | "headings" | "open_tasks" | "completed_tasks" => {
+            Some(ValueType::Number)
+        }
+        "created" | "modified" => Some(ValueType::Date),
+        _ => None,
+    }
+}
+
+// Value of the statistic a key refers to, if any (and known for the page)
+fn statistic(key: &str, page: &Page) -> Option<Value> {
+    let stats = &page.stats;
+    let count = |count: usize| Some(Value::Number(count as f64));
+    let date = |time: Option<std::time::SystemTime>| {
+        time.map(|time| Value::Date(DateTime::<Local>::from(time).date_naive()))
+    };
+
+    match key {
+        "words" => count(stats.words),
+        "characters" => count(stats.characters),
+        "size" => Some(Value::Number(stats.size as f64)),
+        "headings" => count(stats.headings.len()),
+        "open_tasks" => count(stats.open_tasks),
+        "completed_tasks" => count(stats.completed_tasks),
+        "created" => date(stats.created),
+        "modified" => date(stats.modified),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        filtering::{evaluate_expr, parse_boolean_expr, parse_boolean_expr_with, ParsingError},
+        PageStats,
+    };
+    use std::collections::BTreeMap;
+
+    fn testing_page() -> Page {
+        let date =
+            |text: &str| PropertyValue::Date(NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap());
+        Page {
+            properties: BTreeMap::from([
+                (
+                    "Status".to_string(),
+                    PropertyValue::Text("Done".to_string()),
+                ),
+                ("priority".to_string(), PropertyValue::Number(2.0)),
+                ("due".to_string(), date("2026-10-01")),
+                (
+                    "aliases".to_string(),
+                    PropertyValue::List(vec![PropertyValue::Text("Plan".to_string())]),
+                ),
+                (
+                    "up".to_string(),
+                    PropertyValue::Link("Projects|all projects".to_string()),
+                ),
+                ("code".to_string(), PropertyValue::Text("123".to_string())),
+                ("Fällig am".to_string(), date("2026-12-24")),
+            ]),
+            stats: PageStats {
+                words: 600,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn property_comparisons() {
+        let page = testing_page();
+        let property_types = PropertyTypes::new([&page]);
+        let matches = |filter: &str| {
+            evaluate_expr(
+                &parse_boolean_expr_with(filter, &property_types, true).unwrap(),
+                &page,
+            )
+        };
+
+        assert!(matches(
+            "[status:done] priority>=2 due<2026-11-01 has:aliases words>500"
+        ));
+        assert!(matches(
+            "[aliases:plan] [up:Projects] priority!=3 [aliases] \"[status: done]\""
+        ));
+        assert!(matches("[code:123] \"fällig am\">2026-12-01"));
+        assert!(!matches("priority>2") && !matches("due>=2026-11-01") && !matches("has:tags"));
+        assert!(!matches("words<=500"));
+    }
+
+    #[test]
+    fn unknown_keys_are_titles() {
+        let property_types = PropertyTypes::new([&testing_page()]);
+        let title = |filter: &str| match parse_boolean_expr_with(filter, &property_types, true) {
+            Ok(BooleanExpr::Filter(filter)) => filter.text,
+            result => panic!("{:?} for {}", result, filter),
+        };
+
+        assert_eq!(title("a=b"), "a=b");
+        assert_eq!(title("Linear=algebra"), "Linear=algebra");
+        assert_eq!(title("x<y"), "x<y");
+        assert_eq!(title("[draft]"), "[draft]");
+        assert_eq!(title("title:priority=2"), "title:priority=2");
+    }
+
+    #[test]
+    fn type_errors() {
+        let property_types = PropertyTypes::new([&testing_page()]);
+        let error = |filter: &str| match parse_boolean_expr_with(filter, &property_types, true) {
+            Err(ParsingError::InvalidComparison(message, span)) => (message, span),
+            result => panic!("{:?} for {}", result, filter),
+        };
+
+        assert_eq!(
+            error("a words>many"),
+            ("`words` is a number, not a text".to_string(), 2..12)
+        );
+        assert_eq!(error("created<5").0, "`created` is a date, not a number");
+        assert_eq!(
+            error("status>done").0,
+            "`>` compares numbers and dates, not a text"
+        );
+        assert_eq!(
+            error("priority=").0,
+            "`priority` is not compared with a value"
+        );
+
+        // Properties are checked against the types of their values in the vault
+        assert_eq!(
+            error("x due<5"),
+            ("`due` is a date, not a number".to_string(), 2..7)
+        );
+        assert_eq!(
+            error("[priority:high]").0,
+            "`priority` is a number, not a text"
+        );
+        assert_eq!(
+            error("priority!=high").0,
+            "`priority` is a number, not a text"
+        );
+
+        // Without the properties of a vault, only statistics are compared
+        assert!(parse_boolean_expr("due<5").is_ok());
+        assert!(parse_boolean_expr("words>many").is_err());
+        assert!(parse_boolean_expr("title:a=b").is_ok());
+    }
+}
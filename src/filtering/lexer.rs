@@ -0,0 +1,151 @@
+//! This module splits filtering expressions into tokens
+//!
+//! - `(` and `)` group expressions, `&` and `|` combine them and `-` negates the following term
+//! - Terms are runs of any other characters (`tag:#project`, `Linear-algebra`), so `-` is only
+//!   an operator at the start of a term
+//! - Double quotes allow spaces and operator characters within terms (`title:"Linear algebra"`)
+//! - A backslash escapes a following special character (`\(`, `\"`, `\\`, `\ `), within and
+//!   outside of quotes, and is kept before any other character
+
+use std::ops::Range;
+
+use crate::filtering::ParsingError;
+
+/// Describes a token of a filtering expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    /// Filter, with quotes and escapes removed
+    Term(String),
+}
+
+impl Token {
+    /// Returns whether the token can start an operand, which is implicitly combined by AND
+    pub fn starts_operand(&self) -> bool {
+        matches!(self, Token::LeftParen | Token::Not | Token::Term(_))
+    }
+}
+
+// Check whether a character has to be escaped to be part of a term
+fn is_escapable(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '&' | '|' | '-' | '"' | '\\')
+}
+
+/// Splits a filtering expression into tokens, along with their byte ranges
+pub fn tokenize(expr: &str) -> Result<Vec<(Token, Range<usize>)>, ParsingError> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let operator = match c {
+            '(' => Some(Token::LeftParen),
+            ')' => Some(Token::RightParen),
+            '&' => Some(Token::And),
+            '|' => Some(Token::Or),
+            '-' => Some(Token::Not),
+            _ => None,
+        };
+        if let Some(operator) = operator {
+            chars.next();
+            tokens.push((operator, start..start + 1));
+            continue;
+        }
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        // Read a term up to the next whitespace or operator character outside of quotes
+        let mut term = String::new();
+        let mut quote_start: Option<usize> = None;
+        let mut end = start;
+        while let Some(&(i, c)) = chars.peek() {
+            if quote_start.is_none() && (c.is_whitespace() || matches!(c, '(' | ')' | '&' | '|')) {
+                break;
+            }
+            chars.next();
+            end = i + c.len_utf8();
+
+            match c {
+                '"' if quote_start.is_some() => quote_start = None,
+                '"' => quote_start = Some(i),
+                // Other backslashes are kept, so that regexes such as `/\d+/` need no escaping
+                '\\' => match chars.next_if(|(_, next)| is_escapable(*next)) {
+                    Some((i, escaped)) => {
+                        term.push(escaped);
+                        end = i + escaped.len_utf8();
+                    }
+                    None => term.push('\\'),
+                },
+                _ => term.push(c),
+            }
+        }
+        if let Some(quote_start) = quote_start {
+            return Err(ParsingError::UnclosedQuote(quote_start..expr.len()));
+        }
+        tokens.push((Token::Term(term), start..end));
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terms_and_operators() {
+        let term = |text: &str| Token::Term(text.to_string());
+        let tokens: Vec<Token> =
+            tokenize(r#"-(title:"Linear algebra" | Linear-algebra)&tag:#a\ b \"q\""#)
+                .unwrap()
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Not,
+                Token::LeftParen,
+                term("title:Linear algebra"),
+                Token::Or,
+                term("Linear-algebra"),
+                Token::RightParen,
+                Token::And,
+                term("tag:#a b"),
+                term("\"q\""),
+            ]
+        );
+        assert_eq!(
+            tokenize("tag:#a title:\"open").unwrap_err(),
+            ParsingError::UnclosedQuote(13..18)
+        );
+        assert_eq!(tokenize("é-(x)").unwrap()[0].1, 0..3);
+    }
+
+    #[test]
+    fn backslashes_in_regexes() {
+        let terms = |expr: &str| -> Vec<Token> {
+            tokenize(expr)
+                .unwrap()
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect()
+        };
+
+        // Backslashes before ordinary characters are kept, others escape the character
+        assert_eq!(terms(r"/\d+/"), vec![Token::Term(r"/\d+/".to_string())]);
+        assert_eq!(
+            terms(r#"title:"/\w+\(\d\)/" a\-b"#),
+            vec![
+                Token::Term(r"title:/\w+(\d)/".to_string()),
+                Token::Term("a-b".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! This module matches the query of a filter against the values of a page
+//!
+//! - `text` matches values containing `text`
+//! - `=text` matches values equal to `text`
+//! - `Chapter*`, `v?.png` match whole values by glob pattern (`*` is any text, `?` any character)
+//! - `/regex/` matches values containing a match of the regular expression
+//! - `~` before a query ignores case (`~vec`, `~=vector`, `~/^vec/`), as does `i` after a regex
+//!
+//! Regular expressions containing `|`, `(`, `)` or spaces have to be quoted (`"/a|b/"`).
+
+use regex::{Regex, RegexBuilder};
+
+/// This enum describes how the query of a filter matches a value
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    /// Values containing the text (lowercase if case is ignored)
+    Contains { text: String, ignore_case: bool },
+    /// Values equal to the text (lowercase if case is ignored)
+    Exact { text: String, ignore_case: bool },
+    /// Values matching a regular expression (or a glob pattern, translated to one)
+    Pattern(Regex),
+}
+
+impl Matcher {
+    /// Parses a query, compiling regular expressions and glob patterns.
+    ///
+    /// Invalid regular expressions are reported with the message of the regex parser.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let (ignore_case, query) = match query.strip_prefix('~') {
+            Some(query) => (true, query),
+            None => (false, query),
+        };
+
+        if let Some(pattern) = query
+            .strip_prefix('/')
+            .filter(|pattern| !pattern.is_empty())
+        {
+            let (pattern, ignore_case) = if let Some(pattern) = pattern.strip_suffix("/i") {
+                (pattern, true)
+            } else if let Some(pattern) = pattern.strip_suffix('/') {
+                (pattern, ignore_case)
+            } else {
+                return Err("regular expression is not closed by `/`".to_string());
+            };
+            return build_regex(pattern, ignore_case).map(Matcher::Pattern);
+        }
+
+        let text = |text: &str| match ignore_case {
+            true => text.to_lowercase(),
+            false => text.to_string(),
+        };
+        if let Some(exact) = query.strip_prefix('=') {
+            return Ok(Matcher::Exact {
+                text: text(exact),
+                ignore_case,
+            });
+        }
+
+        if query.contains(['*', '?']) {
+            let pattern: String = query
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_string(),
+                    '?' => ".".to_string(),
+                    _ => regex::escape(&c.to_string()),
+                })
+                .collect();
+            return build_regex(&format!("^{}$", pattern), ignore_case).map(Matcher::Pattern);
+        }
+
+        Ok(Matcher::Contains {
+            text: text(query),
+            ignore_case,
+        })
+    }
+
+    /// Checks whether a value matches the query
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Matcher::Contains { text, ignore_case } => match ignore_case {
+                true => value.to_lowercase().contains(text.as_str()),
+                false => value.contains(text.as_str()),
+            },
+            Matcher::Exact { text, ignore_case } => match ignore_case {
+                true => value.to_lowercase() == *text,
+                false => value == text,
+            },
+            Matcher::Pattern(regex) => regex.is_match(value),
+        }
+    }
+
+    /// Returns the text of a plain query (without `=`, `~`, globs or regex)
+    pub fn plain_text(&self) -> Option<&str> {
+        match self {
+            Matcher::Contains {
+                text,
+                ignore_case: false,
+            } => Some(text),
+            _ => None,
+        }
+    }
+}
+
+// Compile a regular expression, describing why it is invalid otherwise
+fn build_regex(pattern: &str, ignore_case: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|error| match error {
+            regex::Error::Syntax(message) => message
+                .lines()
+                .last()
+                .unwrap_or_default()
+                .trim_start_matches("error: ")
+                .to_string(),
+            error => error.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_kinds() {
+        let matches = |query: &str, value: &str| Matcher::parse(query).unwrap().matches(value);
+
+        assert!(matches("Vec", "Vectors space notes") && !matches("vec", "Vector"));
+        assert!(matches("~vec", "Vector") && matches("~=vector", "Vector"));
+        assert!(matches("=Vector", "Vector") && !matches("=Vector", "Vectors"));
+        assert!(matches("/^Vec(tor)?$/", "Vector") && !matches("/^Vec$/", "Vector"));
+        assert!(matches("/^vec/i", "Vector") && matches("~/^vec/", "Vector"));
+        assert!(matches("Vec*", "Vector") && !matches("Vec?", "Vector"));
+        assert!(matches("~*.PNG", "image.png") && !matches("*.png", "image.png.md"));
+
+        assert!(Matcher::parse("/(unclosed/").is_err());
+        assert!(Matcher::parse("/a").is_err());
+        assert_eq!(Matcher::parse("/").unwrap().plain_text(), Some("/"));
+    }
+}
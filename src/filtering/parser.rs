@@ -0,0 +1,333 @@
+//! This module parses the tokens of a filtering expression into a [BooleanExpr]
+//!
+//! Operators bind from strongest to weakest as NOT (`-`), AND (`&`), OR (`|`), and AND and OR
+//! are left-associative. Adjacent operands are combined by AND, so `a b` equals `a & b`.
+//!
+//! ```text
+//! or      = and { "|" and }
+//! and     = not { ["&"] not }
+//! not     = "-" not | operand
+//! operand = term | "(" or ")"
+//! ```
+
+use std::{iter::Peekable, ops::Range, vec::IntoIter};
+
+use crate::filtering::{
+    comparison::{parse_comparison, PropertyTypes},
+    lexer::Token,
+    misspelled_field, BooleanExpr, Filter, ParsingError,
+};
+
+/// Parses the tokens of a filtering expression, which must not be empty.
+///
+/// Comparisons are checked against the types of the properties in `property_types`, and
+/// full-text filters are reported unless `content_indexed`.
+pub fn parse_tokens(
+    tokens: Vec<(Token, Range<usize>)>,
+    property_types: &PropertyTypes,
+    content_indexed: bool,
+) -> Result<BooleanExpr, ParsingError> {
+    let mut parser = Parser {
+        tokens: tokens.into_iter().peekable(),
+        property_types,
+        content_indexed,
+    };
+
+    let expr = parser.parse_or(None)?;
+    match parser.tokens.next() {
+        None => Ok(expr),
+        Some((Token::RightParen, span)) => Err(ParsingError::UnopenedParenthesis(span)),
+        // Operands would have been combined by AND, so only an operator can be left
+        Some((token, span)) => Err(ParsingError::MissingOperand(token_name(&token), span)),
+    }
+}
+
+// Operator an operand belongs to, along with its byte range
+type Operator = Option<(Token, Range<usize>)>;
+
+// Recursive descent parser, with one function per precedence level
+struct Parser<'a> {
+    tokens: Peekable<IntoIter<(Token, Range<usize>)>>,
+    property_types: &'a PropertyTypes,
+    content_indexed: bool,
+}
+
+impl Parser<'_> {
+    // Take the next token if it equals `token`
+    fn next_if(&mut self, token: &Token) -> Operator {
+        self.tokens.next_if(|(next, _)| next == token)
+    }
+
+    // Parse operands combined by OR. `operator` is the operator the operands belong to,
+    // which is reported if the first operand is missing.
+    fn parse_or(&mut self, operator: Operator) -> Result<BooleanExpr, ParsingError> {
+        let mut expr = self.parse_and(operator)?;
+        while let Some(operator) = self.next_if(&Token::Or) {
+            let right = self.parse_and(Some(operator))?;
+            expr = BooleanExpr::Or(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    // Parse operands combined by AND, explicitly or by being adjacent
+    fn parse_and(&mut self, operator: Operator) -> Result<BooleanExpr, ParsingError> {
+        let mut expr = self.parse_not(operator)?;
+        loop {
+            let operator = match self.next_if(&Token::And) {
+                Some(operator) => Some(operator),
+                None if self
+                    .tokens
+                    .peek()
+                    .is_some_and(|(token, _)| token.starts_operand()) =>
+                {
+                    None
+                }
+                None => return Ok(expr),
+            };
+            let right = self.parse_not(operator)?;
+            expr = BooleanExpr::And(Box::new(expr), Box::new(right));
+        }
+    }
+
+    // Parse a (repeatedly) negated operand
+    fn parse_not(&mut self, operator: Operator) -> Result<BooleanExpr, ParsingError> {
+        if let Some(not) = self.next_if(&Token::Not) {
+            let inner = self.parse_not(Some(not))?;
+            return Ok(BooleanExpr::Not(Box::new(inner)));
+        }
+        self.parse_operand(operator)
+    }
+
+    // Parse a term (a comparison or a filter) or a parenthesized expression
+    fn parse_operand(&mut self, operator: Operator) -> Result<BooleanExpr, ParsingError> {
+        match (self.tokens.next(), operator) {
+            (Some((Token::Term(term), span)), _) => {
+                if let Some(comparison) = parse_comparison(&term, self.property_types) {
+                    return comparison
+                        .map_err(|message| ParsingError::InvalidComparison(message, span));
+                }
+                match misspelled_field(&term) {
+                    Some((field, suggestion)) => Err(ParsingError::UnknownField {
+                        field: field.to_string(),
+                        suggestion: suggestion.to_string(),
+                        span: span.start..(span.start + field.len()).min(span.end),
+                    }),
+                    None => match Filter::parse(&term) {
+                        Ok(filter) if filter.field.is_full_text() && !self.content_indexed => {
+                            Err(ParsingError::NotIndexed(span))
+                        }
+                        Ok(filter) => Ok(BooleanExpr::Filter(filter)),
+                        Err(message) => Err(ParsingError::InvalidQuery(message, span)),
+                    },
+                }
+            }
+            (Some((Token::LeftParen, open)), _) => {
+                let expr = self.parse_or(Some((Token::LeftParen, open.clone())))?;
+                match self.tokens.next() {
+                    Some((Token::RightParen, _)) => Ok(expr),
+                    _ => Err(ParsingError::UnclosedParenthesis(open)),
+                }
+            }
+            // An operand is missing after an operator (`a &`), or before one (`& a`)
+            (_, Some((token, span))) | (Some((token, span)), None) => {
+                Err(ParsingError::MissingOperand(token_name(&token), span))
+            }
+            // Implicit AND is only assumed before the start of an operand
+            (None, None) => unreachable!("parse_tokens requires at least one token"),
+        }
+    }
+}
+
+// Name a token in error messages
+fn token_name(token: &Token) -> String {
+    match token {
+        Token::LeftParen => "(",
+        Token::RightParen => ")",
+        Token::And => "&",
+        Token::Or => "|",
+        Token::Not => "-",
+        Token::Term(term) => term,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filtering::parse_boolean_expr;
+    use proptest::prelude::*;
+
+    fn filter(text: &str) -> BooleanExpr {
+        BooleanExpr::Filter(Filter::parse(text).unwrap())
+    }
+    fn not(inner: BooleanExpr) -> BooleanExpr {
+        BooleanExpr::Not(Box::new(inner))
+    }
+    fn and(left: BooleanExpr, right: BooleanExpr) -> BooleanExpr {
+        BooleanExpr::And(Box::new(left), Box::new(right))
+    }
+    fn or(left: BooleanExpr, right: BooleanExpr) -> BooleanExpr {
+        BooleanExpr::Or(Box::new(left), Box::new(right))
+    }
+
+    #[test]
+    fn precedence_and_implicit_and() {
+        assert_eq!(
+            parse_boolean_expr("a | b & -c d").unwrap(),
+            or(
+                filter("a"),
+                and(and(filter("b"), not(filter("c"))), filter("d"))
+            )
+        );
+        assert_eq!(
+            parse_boolean_expr("-(a | Linear-algebra) title:\"x y\"").unwrap(),
+            and(
+                not(or(filter("a"), filter("Linear-algebra"))),
+                filter("title:x y")
+            )
+        );
+    }
+
+    #[test]
+    fn parsing_errors() {
+        let error = |expr: &str| parse_boolean_expr(expr).unwrap_err();
+        let missing = |operator: &str, span: Range<usize>| {
+            ParsingError::MissingOperand(operator.to_string(), span)
+        };
+
+        assert_eq!(error("(a | (b)"), ParsingError::UnclosedParenthesis(0..1));
+        assert_eq!(error("a) b"), ParsingError::UnopenedParenthesis(1..2));
+        assert_eq!(error("a &"), missing("&", 2..3));
+        assert_eq!(error("| a"), missing("|", 0..1));
+        assert_eq!(error("a & | b"), missing("&", 2..3));
+        assert_eq!(error("a ()"), missing("(", 2..3));
+        assert_eq!(
+            error("a tags:#b"),
+            ParsingError::UnknownField {
+                field: "tags:".to_string(),
+                suggestion: "tag:#".to_string(),
+                span: 2..7
+            }
+        );
+        assert_eq!(error("tag:b").hint(), "did you mean `tag:#`?");
+        assert!(matches!(
+            error(r#"a title:"/(b/""#),
+            ParsingError::InvalidQuery(_, span) if span == (2..14)
+        ));
+        assert!(parse_boolean_expr("Chapter:Intro Title").is_ok());
+    }
+
+    // Names of filters, including characters which have to be quoted or escaped
+    const NAMES: &[&str] = &[
+        "a",
+        "tag:#b",
+        "Linear-algebra",
+        "title:two words",
+        "-leading",
+        "x&y|(z)",
+        "quote\"d",
+        "back\\slash",
+    ];
+
+    // Expression tree to compare the parser against, with filters as indices into NAMES
+    #[derive(Debug, Clone)]
+    enum Reference {
+        Filter(usize),
+        Not(Box<Reference>),
+        And(Box<Reference>, Box<Reference>),
+        Or(Box<Reference>, Box<Reference>),
+    }
+
+    fn reference_strategy() -> impl Strategy<Value = Reference> {
+        let leaf = (0..NAMES.len()).prop_map(Reference::Filter);
+        leaf.prop_recursive(6, 48, 2, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|e| Reference::Not(Box::new(e))),
+                (inner.clone(), inner.clone())
+                    .prop_map(|(l, r)| Reference::And(Box::new(l), Box::new(r))),
+                (inner.clone(), inner).prop_map(|(l, r)| Reference::Or(Box::new(l), Box::new(r))),
+            ]
+        })
+    }
+
+    // Reference evaluator, with the truth value of every filter given by `values`
+    fn evaluate_reference(expr: &Reference, values: &[bool]) -> bool {
+        match expr {
+            Reference::Filter(index) => values[*index],
+            Reference::Not(inner) => !evaluate_reference(inner, values),
+            Reference::And(l, r) => evaluate_reference(l, values) && evaluate_reference(r, values),
+            Reference::Or(l, r) => evaluate_reference(l, values) || evaluate_reference(r, values),
+        }
+    }
+
+    fn evaluate_parsed(expr: &BooleanExpr, values: &[bool]) -> bool {
+        match expr {
+            BooleanExpr::Filter(filter) => {
+                values[NAMES.iter().position(|name| *name == filter.text).unwrap()]
+            }
+            BooleanExpr::Not(inner) => !evaluate_parsed(inner, values),
+            BooleanExpr::And(l, r) => evaluate_parsed(l, values) && evaluate_parsed(r, values),
+            BooleanExpr::Or(l, r) => evaluate_parsed(l, values) || evaluate_parsed(r, values),
+            BooleanExpr::Compare(_) | BooleanExpr::Has(_) => {
+                unreachable!("no comparisons in NAMES")
+            }
+        }
+    }
+
+    // Write an expression with as few parentheses as its precedence allows, unless `parens`.
+    // `implicit_and` leaves out `&`, `spaces` puts spaces around operators.
+    fn render(expr: &Reference, min_precedence: u8, style: (bool, bool, bool)) -> String {
+        let (implicit_and, spaces, parens) = style;
+        let (precedence, text) = match expr {
+            Reference::Filter(index) => {
+                let name = NAMES[*index];
+                let text = if name.starts_with('-') || name.contains([' ', '&', '|', '(', ')']) {
+                    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+                } else {
+                    name.replace('\\', "\\\\").replace('"', "\\\"")
+                };
+                (4, text)
+            }
+            Reference::Not(inner) => (3, format!("-{}", render(inner, 3, style))),
+            Reference::And(l, r) => {
+                let operator = match (implicit_and, spaces) {
+                    (true, _) => " ",
+                    (false, true) => " & ",
+                    (false, false) => "&",
+                };
+                let text = render(l, 2, style) + operator + &render(r, 3, style);
+                (2, text)
+            }
+            Reference::Or(l, r) => {
+                let operator = if spaces { " | " } else { "|" };
+                (1, render(l, 1, style) + operator + &render(r, 2, style))
+            }
+        };
+
+        if precedence < min_precedence || (parens && precedence < 4) {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn parser_matches_reference(
+            expr in reference_strategy(),
+            values in proptest::collection::vec(any::<bool>(), NAMES.len()),
+            style in any::<(bool, bool, bool)>(),
+        ) {
+            let text = render(&expr, 0, style);
+            let parsed = parse_boolean_expr(&text)
+                .map_err(|error| TestCaseError::fail(format!("{:?} for {}", error, text)))?;
+
+            prop_assert_eq!(
+                evaluate_parsed(&parsed, &values),
+                evaluate_reference(&expr, &values),
+                "{}",
+                text
+            );
+        }
+    }
+}
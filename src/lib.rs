@@ -1,54 +1,345 @@
-use filtering::{evaluate_expr, parse_boolean_expr, ParsingError};
+use filtering::{
+    comparison::PropertyTypes, evaluate_expr, parse_boolean_expr_with, BooleanExpr, ParsingError,
+};
 use petgraph::{
     graph::{EdgeIndex, NodeIndex},
-    Graph,
+    visit::EdgeRef,
+    Direction, Graph,
 };
 use rand_distr::{Distribution, Normal};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    time::SystemTime,
+};
 
 pub mod app;
 pub mod filtering;
 pub mod vault_parser;
 
 /// This struct stores the Markdown page information.
-/// 
+///
 /// Pages are made when the [vault_parser] extracts the contents of a vault,
 /// which then act as node weights in a [Graph](petgraph::Graph) instance.
 /// [Nodes](Node) handle the graphical representations of Pages.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Page {
     /// Title of the page
     pub title: String,
+    /// Vault-relative path of the page, which identifies it within the vault
+    ///
+    /// For empty pages, this is the path at which the page would be created.
+    pub path: PathBuf,
     /// Tags of the page
     pub tags: Vec<String>,
+    /// Properties of the page (from the frontmatter)
+    pub properties: BTreeMap<String, PropertyValue>,
     /// Whether the page is empty
     pub empty: bool,
-    links: Vec<String>,
+    /// Whether the page is a Markdown note or an attachment
+    pub kind: PageKind,
+    /// Size, contents and timestamps of the page's file
+    pub stats: PageStats,
+    /// Text of the note, if the vault was scanned with
+    /// [index_content](vault_parser::scanner::ScanConfig::index_content) (or unlinked mentions)
+    pub content: Option<String>,
+    links: Vec<Link>,
+}
+
+/// This struct stores statistics about the file and contents of a [Page].
+///
+/// Content statistics are only gathered for Markdown notes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PageStats {
+    /// Number of words in the body of the note
+    pub words: usize,
+    /// Number of characters in the body of the note (without line breaks)
+    pub characters: usize,
+    /// Headings of the note, in order
+    pub headings: Vec<Heading>,
+    /// Number of open tasks (`- [ ]`)
+    pub open_tasks: usize,
+    /// Number of completed tasks (`- [x]`)
+    pub completed_tasks: usize,
+    /// Creation time of the file (if supported by the platform)
+    pub created: Option<SystemTime>,
+    /// Modification time of the file (if supported by the platform)
+    pub modified: Option<SystemTime>,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+/// This struct stores a heading of a note's outline.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Heading {
+    /// Level of the heading (1 for `#`, up to 6 for `######`)
+    pub level: u8,
+    /// Text of the heading
+    pub text: String,
+    /// Line of the heading (starting at 1)
+    pub line: usize,
+}
+
+/// This enum describes which kind of file a [Page] stands for.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PageKind {
+    /// Markdown note
+    #[default]
+    Note,
+    /// Attachment, such as an image, PDF or audio file
+    Attachment {
+        /// Lowercase file extension (`png`, `pdf`, ...)
+        file_type: String,
+        /// Size of the file in bytes
+        size: u64,
+    },
+    /// Obsidian Canvas (`.canvas` file)
+    Canvas,
+    /// Web page linked from a canvas, whose path is its URL
+    Url,
+}
+
+/// This struct stores a link from a [Page] to another page.
+///
+/// Besides the linked page, a link can point to a specific heading or block within that page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Link {
+    /// Linked page name or path, as written in the note
+    pub target: String,
+    /// Vault-relative path of the linked page (filled in when the link is resolved)
+    pub path: PathBuf,
+    /// Whether the page is linked or embedded
+    pub kind: LinkKind,
+    /// Heading within the linked page (`[[Page#Heading]]`)
+    pub heading: Option<String>,
+    /// Block reference within the linked page (`[[Page#^block]]`)
+    pub block: Option<String>,
+    /// Display text of the link (`[[Page|Display text]]`)
+    pub alias: Option<String>,
+    /// Line of the link in the linking page (starting at 1)
+    pub line: usize,
+    /// Column of the link within its line, in characters (starting at 1)
+    pub column: usize,
+    /// Page the link starts from, as written, if not the linking page (for canvas relations)
+    pub source: Option<String>,
+    /// Vault-relative path of the page the link starts from (filled in when the link is resolved)
+    pub source_path: Option<PathBuf>,
+    /// Property whose value contains the link (`parent:: [[Page]]`)
+    pub property: Option<String>,
+    /// Sentence around the link in the linking page, as written
+    pub context: Option<String>,
+}
+
+/// This enum describes how a [Link] refers to its target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum LinkKind {
+    /// Regular link (`[[Page]]` or `[text](Page.md)`)
+    #[default]
+    Link,
+    /// Embedded page or file (`![[Page]]` or `![text](image.png)`)
+    Embed,
+    /// Relation drawn as an edge between two cards of a canvas
+    Relation,
+    /// Implicit link from a note to its parent in a Dendron hierarchy (`a.b.c` to `a.b`)
+    Hierarchy,
+    /// Title or alias of a page appearing in the text of a note without a link
+    Mention,
+}
+
+/// This struct stores the links of one kind from a [Page] to another, which share an edge.
+///
+/// Edges act as edge weights in a [Graph](petgraph::Graph) instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Edge {
+    /// Whether the page is linked, embedded, related on a canvas, a parent in a hierarchy or mentioned
+    pub kind: LinkKind,
+    /// Number of links the edge stands for
+    pub count: usize,
+    /// Lines of the links in the linking page
+    pub lines: Vec<usize>,
+    /// Display texts of the links (or labels of canvas relations)
+    pub aliases: Vec<String>,
+    /// Headings (`Heading`) and blocks (`^block`) the links point to
+    pub anchors: Vec<String>,
+    /// Properties whose values contain the links (frontmatter properties or inline fields)
+    pub properties: Vec<String>,
+    /// Sentences around the links, in the same order as `lines` (empty for canvas relations)
+    pub contexts: Vec<String>,
+}
+
+impl Edge {
+    /// Creates an edge standing for a single link
+    pub fn new(link: &Link) -> Self {
+        let mut edge = Self {
+            kind: link.kind,
+            ..Default::default()
+        };
+        edge.add_link(link);
+        edge
+    }
+
+    /// Adds another link to the edge
+    pub fn add_link(&mut self, link: &Link) {
+        self.count += 1;
+        self.lines.push(link.line);
+
+        let anchor = match (&link.heading, &link.block) {
+            (_, Some(block)) => Some(format!("^{}", block)),
+            (Some(heading), None) => Some(heading.clone()),
+            (None, None) => None,
+        };
+        for (values, value) in [
+            (&mut self.aliases, link.alias.clone()),
+            (&mut self.anchors, anchor),
+            (&mut self.properties, link.property.clone()),
+        ] {
+            if let Some(value) = value.filter(|value| !values.contains(value)) {
+                values.push(value)
+            }
+        }
+        self.contexts.push(link.context.clone().unwrap_or_default());
+    }
+
+    /// Returns the line and surrounding sentence of every link with a known context
+    pub fn occurrences(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.lines
+            .iter()
+            .zip(&self.contexts)
+            .filter(|(_, context)| !context.is_empty())
+            .map(|(line, context)| (*line, context.as_str()))
+    }
+
+    /// Returns whether some links of the edge come from property values
+    pub fn is_property(&self) -> bool {
+        !self.properties.is_empty()
+    }
+
+    /// Returns the relative strength of the edge, which grows with the number of its links
+    pub fn strength(&self) -> f32 {
+        1.0 + (self.count.max(1) as f32).ln()
+    }
+}
+
+/// This struct describes which edges of a [GraphView] are shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeFilter {
+    /// Whether links are shown
+    pub links: bool,
+    /// Whether embeds are shown
+    pub embeds: bool,
+    /// Whether canvas relations are shown
+    pub relations: bool,
+    /// Whether links to parents in a hierarchy are shown
+    pub hierarchy: bool,
+    /// Whether unlinked mentions are shown
+    pub mentions: bool,
+    /// Whether edges with links from property values are shown
+    pub properties: bool,
+    /// Minimum number of links an edge has to stand for
+    pub min_count: usize,
+}
+
+impl Default for EdgeFilter {
+    fn default() -> Self {
+        Self {
+            links: true,
+            embeds: true,
+            relations: true,
+            hierarchy: true,
+            mentions: true,
+            properties: true,
+            min_count: 1,
+        }
+    }
+}
+
+impl EdgeFilter {
+    /// Checks whether an edge passes the filter
+    pub fn matches(&self, edge: &Edge) -> bool {
+        let kind_shown = match edge.kind {
+            LinkKind::Link => self.links,
+            LinkKind::Embed => self.embeds,
+            LinkKind::Relation => self.relations,
+            LinkKind::Hierarchy => self.hierarchy,
+            LinkKind::Mention => self.mentions,
+        };
+
+        kind_shown && edge.count >= self.min_count && (self.properties || !edge.is_property())
+    }
+}
+
+/// This enum stores the typed value of a [Page] property.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum PropertyValue {
+    Text(String),
+    Number(f64),
+    Boolean(bool),
+    Date(chrono::NaiveDate),
+    DateTime(chrono::NaiveDateTime),
+    List(Vec<PropertyValue>),
+    /// Link to a page, holding the inside of the wikilink (`Page#Heading|Display text`)
+    Link(String),
+}
+
+impl Page {
+    /// Returns the links of the page
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Returns the alternative names of the page (from the `aliases` property)
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases = Vec::new();
+
+        for (key, value) in &self.properties {
+            if key.eq_ignore_ascii_case("aliases") || key.eq_ignore_ascii_case("alias") {
+                match value {
+                    PropertyValue::List(items) => {
+                        for item in items {
+                            if let PropertyValue::Text(alias) = item {
+                                aliases.push(alias.trim().to_string())
+                            }
+                        }
+                    }
+                    PropertyValue::Text(text) => {
+                        aliases.extend(text.split(',').map(|alias| alias.trim().to_string()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        aliases.retain(|alias| !alias.is_empty());
+        aliases
+    }
 }
 
 /// This struct stores the graphical representation of a [Page].
-/// 
-/// Nodes are used by the [GraphView] struct to keep track of the graph layout, filtering and grouping. 
+///
+/// Nodes are used by the [GraphView] struct to keep track of the graph layout, filtering and grouping.
 pub struct Node {
     /// Node index corresponding to the [Page] in the [Graph](petgraph::Graph)
     pub node_index: NodeIndex,
     /// Position in pixels (relative to frame center)
-    pub frame_pos: egui::Vec2, 
+    pub frame_pos: egui::Vec2,
     /// Whether the node is visible (for filtering, etc.)
     pub visible: bool,
 }
 
 /// This struct handles the graphical representation of the node graph.
 pub struct GraphView {
-    graph: Graph<Page, ()>,
+    graph: Graph<Page, Edge>,
     nodes: HashMap<NodeIndex, Node>,
+    edge_filter: EdgeFilter,
+    property_types: PropertyTypes,
+    content_indexed: bool,
 }
 
 impl Node {
     pub fn new(node_index: NodeIndex, pos: egui::Vec2) -> Self {
         Self {
-            node_index: node_index,
+            node_index,
             frame_pos: pos,
             visible: true,
         }
@@ -56,11 +347,10 @@ impl Node {
 }
 
 impl GraphView {
-    pub fn new(graph: Graph<Page, ()>) -> Self {
+    pub fn new(graph: Graph<Page, Edge>) -> Self {
         // Save nodes to hashmap
         let nodes: HashMap<NodeIndex, Node> = graph
             .node_indices()
-            .into_iter()
             .map(|i| {
                 let normal = Normal::new(0.0, 100.0).unwrap();
                 (
@@ -77,8 +367,11 @@ impl GraphView {
             .collect();
 
         Self {
-            graph: graph,
-            nodes: nodes,
+            property_types: PropertyTypes::new(graph.node_weights()),
+            content_indexed: graph.node_weights().any(|page| page.content.is_some()),
+            graph,
+            nodes,
+            edge_filter: EdgeFilter::default(),
         }
     }
 
@@ -90,11 +383,11 @@ impl GraphView {
             .collect()
     }
 
-    /// Return a vector of edge start and end positions in screenspace
+    /// Return a vector of edge start and end positions in screenspace, for edges passing the edge filter
     pub fn edge_start_end_positions(&self) -> Vec<(EdgeIndex, egui::Vec2, egui::Vec2)> {
         self.graph
             .edge_indices()
-            .into_iter()
+            .filter(|edge| self.edge_is_visible(*edge))
             .map(|edge| {
                 let (start_index, end_index) = self.graph.edge_endpoints(edge).unwrap();
 
@@ -109,11 +402,7 @@ impl GraphView {
 
     /// Returns a copy of the associated page of a node
     pub fn node_page(&self, index: &NodeIndex) -> Option<Page> {
-        if let Some(page) = self.graph.node_weight(*index) {
-            Some(page.clone())
-        } else {
-            None
-        }
+        self.graph.node_weight(*index).cloned()
     }
 
     /// Checks if the associated page of a node is empty
@@ -134,6 +423,13 @@ impl GraphView {
         }
     }
 
+    /// Checks if the associated page of a node is an attachment
+    pub fn node_is_attachment(&self, index: NodeIndex) -> bool {
+        self.graph
+            .node_weight(index)
+            .is_some_and(|page| matches!(page.kind, PageKind::Attachment { .. }))
+    }
+
     /// Returns the tags of the associated page of a node
     pub fn node_tags(&self, index: NodeIndex) -> Vec<String> {
         if let Some(page) = self.graph.node_weight(index) {
@@ -143,6 +439,165 @@ impl GraphView {
         }
     }
 
+    /// Returns the file and content statistics of the associated page of a node
+    pub fn node_stats(&self, index: NodeIndex) -> PageStats {
+        if let Some(page) = self.graph.node_weight(index) {
+            page.stats.clone()
+        } else {
+            PageStats::default()
+        }
+    }
+
+    /// Returns the links an edge stands for
+    pub fn edge(&self, index: EdgeIndex) -> Option<&Edge> {
+        self.graph.edge_weight(index)
+    }
+
+    /// Returns whether an edge is a link, an embed, a canvas relation, a hierarchy link or a mention
+    pub fn edge_kind(&self, index: EdgeIndex) -> Option<LinkKind> {
+        self.graph.edge_weight(index).map(|edge| edge.kind)
+    }
+
+    /// Returns the names of the properties whose values contain the links of an edge
+    pub fn edge_labels(&self, index: EdgeIndex) -> Vec<String> {
+        self.graph
+            .edge_weight(index)
+            .map(|edge| edge.properties.clone())
+            .unwrap_or_default()
+    }
+
+    /// Checks if an edge passes the edge filter
+    pub fn edge_is_visible(&self, index: EdgeIndex) -> bool {
+        self.graph
+            .edge_weight(index)
+            .is_some_and(|edge| self.edge_filter.matches(edge))
+    }
+
+    /// Returns the unlinked mentions between visible nodes, as the mentioning and mentioned node
+    /// along with the sentences of the mentions
+    pub fn unlinked_mentions(&self) -> Vec<(NodeIndex, NodeIndex, &[String])> {
+        self.graph
+            .edge_references()
+            .filter(|edge| edge.weight().kind == LinkKind::Mention)
+            .filter(|edge| {
+                [edge.source(), edge.target()]
+                    .iter()
+                    .all(|index| self.nodes.get(index).is_some_and(|node| node.visible))
+            })
+            .map(|edge| {
+                (
+                    edge.source(),
+                    edge.target(),
+                    edge.weight().contexts.as_slice(),
+                )
+            })
+            .collect()
+    }
+
+    /// Sets which edges are shown (and pull their nodes together)
+    pub fn set_edge_filter(&mut self, edge_filter: EdgeFilter) {
+        self.edge_filter = edge_filter;
+    }
+
+    /// Parses a filtering expression, checking comparisons against the types of the properties
+    /// of the pages, and full-text filters against whether the text of the pages is kept
+    pub fn parse_filter(&self, filter_query: &str) -> Result<BooleanExpr, ParsingError> {
+        parse_boolean_expr_with(filter_query, &self.property_types, self.content_indexed)
+    }
+
+    /// Returns a copy of all pages in the graph
+    pub fn pages(&self) -> Vec<Page> {
+        self.graph.node_weights().cloned().collect()
+    }
+
+    /// Replaces the pages of the graph, keeping the nodes (and positions) of pages which still exist.
+    ///
+    /// Pages are matched by path. Nodes of new pages are placed near the nodes they are linked with,
+    /// and all edges are rebuilt from the links of the new pages.
+    pub fn update_pages(&mut self, pages: Vec<Page>) {
+        let mut new_pages: HashMap<PathBuf, Page> = HashMap::new();
+        for page in pages {
+            new_pages.entry(page.path.clone()).or_insert(page);
+        }
+
+        self.graph.clear_edges();
+
+        // Remove the nodes of deleted pages. Petgraph moves the last node into the index
+        // of a removed node, so nodes are removed from the highest index down.
+        let mut removed: Vec<NodeIndex> = self
+            .graph
+            .node_indices()
+            .filter(|index| !new_pages.contains_key(&self.graph[*index].path))
+            .collect();
+        removed.sort_by(|a, b| b.cmp(a));
+        for index in removed {
+            let last_index = NodeIndex::new(self.graph.node_count() - 1);
+            self.graph.remove_node(index);
+            self.nodes.remove(&index);
+
+            if let Some(mut node) = self
+                .nodes
+                .remove(&last_index)
+                .filter(|_| index != last_index)
+            {
+                node.node_index = index;
+                self.nodes.insert(index, node);
+            }
+        }
+
+        // Update the pages of the remaining nodes
+        let mut path_to_node: HashMap<PathBuf, NodeIndex> = HashMap::new();
+        for index in self.graph.node_indices() {
+            if let Some(page) = new_pages.remove(&self.graph[index].path) {
+                self.graph[index] = page;
+            }
+            path_to_node.insert(self.graph[index].path.clone(), index);
+        }
+
+        // Add nodes for new pages
+        let mut new_pages: Vec<Page> = new_pages.into_values().collect();
+        new_pages.sort_by(|a, b| a.path.cmp(&b.path));
+        let added: Vec<NodeIndex> = new_pages
+            .into_iter()
+            .map(|page| {
+                let path = page.path.clone();
+                let index = self.graph.add_node(page);
+                path_to_node.insert(path, index);
+                index
+            })
+            .collect();
+
+        vault_parser::add_link_edges(&mut self.graph, &path_to_node);
+        self.property_types = PropertyTypes::new(self.graph.node_weights());
+        self.content_indexed = self.graph.node_weights().any(|page| page.content.is_some());
+
+        // Place new nodes around the average position of their already placed neighbours
+        let normal = Normal::new(0.0, 100.0).unwrap();
+        for index in added {
+            let neighbour_positions: Vec<egui::Vec2> = self
+                .graph
+                .neighbors_undirected(index)
+                .filter_map(|neighbour| self.nodes.get(&neighbour))
+                .map(|node| node.frame_pos)
+                .collect();
+            let offset = egui::Vec2::new(
+                normal.sample(&mut rand::thread_rng()),
+                normal.sample(&mut rand::thread_rng()),
+            );
+
+            let pos = if neighbour_positions.is_empty() {
+                offset
+            } else {
+                neighbour_positions
+                    .iter()
+                    .fold(egui::Vec2::ZERO, |sum, pos| sum + *pos)
+                    / neighbour_positions.len() as f32
+                    + offset * 0.25
+            };
+            self.nodes.insert(index, Node::new(index, pos));
+        }
+    }
+
     /// Returns the visibility of a node
     pub fn node_is_visible(&self, index: NodeIndex) -> bool {
         let node = self.nodes.get(&index).expect("Node not found");
@@ -159,7 +614,7 @@ impl GraphView {
 
     /// Set node visibilities based on a filtering expression
     pub fn filter_nodes(&mut self, filter_query: &str) -> Option<ParsingError> {
-        let expr_result = parse_boolean_expr(&filter_query);
+        let expr_result = self.parse_filter(filter_query);
 
         // Check if bool_expr is parsed successfully
         match expr_result {
@@ -171,31 +626,30 @@ impl GraphView {
                     // Extract page from node and evaluate expression
                     if let Some(page) = self.graph.node_weight(*node_index) {
                         if !page.empty {
-                            if evaluate_expr(&bool_expr, &page) {
-                                node.visible = true
-                            } else {
-                                node.visible = false
-                            }
+                            node.visible = evaluate_expr(&bool_expr, page);
                         } else {
                             empty_pages.push((*node_index, page.clone()))
                         }
                     }
                 }
 
-                // Evaluate empty pages based on parent node
-                for (node_index, _page) in empty_pages {
-                    let parent_node_index = self
+                // Evaluate empty pages based on parent node, the first non-empty neighbour (whose
+                // visibility is already known). Empty pages may have no such parent (such as
+                // after an update), in which case they are evaluated themselves.
+                for (node_index, page) in empty_pages {
+                    let parent = self
                         .graph
                         .neighbors_undirected(node_index)
-                        .nth(0)
-                        .expect("Empty node with no parent");
-                    let parent_is_visible = self.nodes.get(&parent_node_index).unwrap().visible;
-                    let mut node = self.nodes.get_mut(&node_index).unwrap();
-
-                    if parent_is_visible {
-                        node.visible = true
-                    } else {
-                        node.visible = false
+                        .find(|neighbour| !self.graph[*neighbour].empty);
+                    let visible = match parent {
+                        Some(parent_node_index) => self
+                            .nodes
+                            .get(&parent_node_index)
+                            .is_some_and(|node| node.visible),
+                        None => evaluate_expr(&bool_expr, &page),
+                    };
+                    if let Some(node) = self.nodes.get_mut(&node_index) {
+                        node.visible = visible;
                     }
                     /*
                     if evaluate_expr(&bool_expr, &page) {
@@ -213,6 +667,7 @@ impl GraphView {
     }
 
     /// Advance the physics by 1 timestep
+    #[allow(clippy::too_many_arguments)]
     pub fn physics_timestep(
         &mut self,
         node_mass: f32,
@@ -234,14 +689,24 @@ impl GraphView {
             if node.visible {
                 let mut accel: egui::Vec2 = egui::Vec2::new(0., 0.);
 
-                // Retrieve neighbor coordinates
-                let neighbors: Vec<egui::Vec2> = self
+                // Retrieve neighbor coordinates, along with the strength of their edges
+                // (unlinked mentions are only potential links, so they do not pull nodes together)
+                let neighbors: Vec<(egui::Vec2, f32)> = self
                     .graph
-                    .neighbors_undirected(*index)
-                    .filter_map(|neigbor_node_index| {
+                    .edges_directed(*index, Direction::Outgoing)
+                    .map(|edge| (edge.target(), edge.weight()))
+                    .chain(
+                        self.graph
+                            .edges_directed(*index, Direction::Incoming)
+                            .map(|edge| (edge.source(), edge.weight())),
+                    )
+                    .filter(|(_, edge)| {
+                        edge.kind != LinkKind::Mention && self.edge_filter.matches(edge)
+                    })
+                    .filter_map(|(neigbor_node_index, edge)| {
                         let neighbor_node = self.nodes.get(&neigbor_node_index).unwrap();
                         if neighbor_node.visible {
-                            Some(neighbor_node.frame_pos)
+                            Some((neighbor_node.frame_pos, edge.strength()))
                         } else {
                             None
                         }
@@ -249,8 +714,8 @@ impl GraphView {
                     .collect();
 
                 // Get acceleration due to springs
-                for neighbor_pos in neighbors.iter() {
-                    let neighbor_accel = spring_constant / node_mass
+                for (neighbor_pos, strength) in neighbors.iter() {
+                    let neighbor_accel = strength * spring_constant / node_mass
                         * (*neighbor_pos - node.frame_pos)
                         * (1.0 - spring_length / ((*neighbor_pos - node.frame_pos).length()) + 1.0)
                             .log10();
@@ -263,8 +728,8 @@ impl GraphView {
                 // Retrieve coordinates of all nodes
                 let node_coords: Vec<egui::Vec2> = self
                     .nodes
-                    .iter()
-                    .filter_map(|(_, other_node)| {
+                    .values()
+                    .filter_map(|other_node| {
                         if other_node.visible {
                             Some(other_node.frame_pos)
                         } else {
@@ -318,33 +783,44 @@ impl GraphView {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
+
+    fn link(target: &str) -> Link {
+        Link {
+            target: target.to_string(),
+            ..Default::default()
+        }
+    }
 
-    fn create_testing_graph() -> (NodeIndex, NodeIndex, NodeIndex, NodeIndex, Graph<Page, ()>) {
+    fn create_testing_graph() -> (
+        NodeIndex,
+        NodeIndex,
+        NodeIndex,
+        NodeIndex,
+        Graph<Page, Edge>,
+    ) {
         // Create graph
-        let mut graph = Graph::<Page, ()>::new();
+        let mut graph = Graph::<Page, Edge>::new();
         let page1 = graph.add_node(Page {
             title: "Page 1".to_string(),
             tags: vec!["tag1".to_string(), "tag2".to_string()],
-            empty: false,
-            links: vec!["Page 2".to_string()],
+            links: vec![link("Page 2")],
+            ..Default::default()
         });
         let page2 = graph.add_node(Page {
             title: "Page 2".to_string(),
-            tags: Vec::default(),
-            empty: false,
-            links: Vec::default(),
+            ..Default::default()
         });
         let page3 = graph.add_node(Page {
             title: "Page 3".to_string(),
-            tags: Vec::default(),
-            empty: false,
-            links: vec!["Page 1".to_string(), "Page 2".to_string()],
+            links: vec![link("Page 1"), link("Page 2")],
+            ..Default::default()
         });
         let page4 = graph.add_node(Page {
             title: "Page 4".to_string(),
             tags: vec!["tag1".to_string()],
-            empty: false,
-            links: vec!["Page 3".to_string()],
+            links: vec![link("Page 3")],
+            ..Default::default()
         });
 
         // Create & filter graphview
@@ -380,4 +856,97 @@ mod tests {
                 && !graphview.node_is_visible(page4)
         )
     }
+
+    #[test]
+    fn graph_filtering_empty_page_without_parent() {
+        let mut graph = Graph::<Page, Edge>::new();
+        let empty = graph.add_node(Page {
+            title: "Orphan".to_string(),
+            empty: true,
+            ..Default::default()
+        });
+        let mut graphview = GraphView::new(graph);
+
+        assert!(graphview.filter_nodes("Orphan").is_none());
+        assert!(graphview.node_is_visible(empty));
+        graphview.filter_nodes("Other");
+        assert!(!graphview.node_is_visible(empty));
+    }
+
+    #[test]
+    fn graph_filtering_linked_empty_pages() {
+        let mut graph = Graph::<Page, Edge>::new();
+        let parent = graph.add_node(Page {
+            title: "Parent".to_string(),
+            ..Default::default()
+        });
+        let empty_pages = ["Empty 1", "Empty 2"].map(|title| {
+            graph.add_node(Page {
+                title: title.to_string(),
+                empty: true,
+                ..Default::default()
+            })
+        });
+        for empty in empty_pages {
+            graph.add_edge(parent, empty, Edge::new(&link("Empty")));
+        }
+        // Added last, so the other empty page is the first neighbour of each
+        graph.add_edge(empty_pages[0], empty_pages[1], Edge::new(&link("Empty 2")));
+        let mut graphview = GraphView::new(graph);
+
+        graphview.filter_nodes("Parent");
+        assert!(empty_pages
+            .iter()
+            .all(|empty| graphview.node_is_visible(*empty)));
+        graphview.filter_nodes("Empty");
+        assert!(empty_pages
+            .iter()
+            .all(|empty| !graphview.node_is_visible(*empty)));
+    }
+
+    #[test]
+    fn graph_update_keeps_positions() {
+        let page = |path: &str, links: &[&str]| Page {
+            title: path.trim_end_matches(".md").to_string(),
+            path: PathBuf::from(path),
+            links: links
+                .iter()
+                .map(|target| Link {
+                    path: PathBuf::from(target),
+                    ..link(target)
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let mut graphview = GraphView::new(vault_parser::pages_to_graph(vec![
+            page("A.md", &["B.md"]),
+            page("B.md", &[]),
+            page("C.md", &["A.md"]),
+        ]));
+        let positions: HashMap<String, egui::Vec2> = graphview
+            .node_positions()
+            .into_iter()
+            .map(|(index, pos)| (graphview.node_title(index), pos))
+            .collect();
+
+        // Delete B (moving C into its node index) and add D
+        graphview.update_pages(vec![
+            page("A.md", &["D.md"]),
+            page("C.md", &["A.md"]),
+            page("D.md", &[]),
+        ]);
+
+        assert_eq!(graphview.graph.node_count(), 3);
+        assert_eq!(graphview.graph.edge_count(), 2);
+        for (index, pos) in graphview.node_positions() {
+            assert_eq!(graphview.nodes[&index].node_index, index);
+            if let Some(old_pos) = positions.get(&graphview.node_title(index)) {
+                assert_eq!(pos, *old_pos);
+            }
+        }
+        assert!(graphview
+            .pages()
+            .iter()
+            .any(|page| page.path == Path::new("D.md")));
+    }
 }
@@ -1,146 +1,1382 @@
 //! This module parses ObsidianMD vaults into Rust-readable structs
 
-use petgraph::Graph;
-use regex::Regex;
+use petgraph::{graph::NodeIndex, visit::EdgeRef, Graph};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
-    fs,
-    io::Read,
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
-use crate::Page;
+use crate::{Edge, Link, LinkKind, Page, PageKind, PageStats, PropertyValue};
+use cache::{CacheEntry, FileState, ParseCache};
+use format::VaultFormat;
+use markdown::LineIndex;
+use mentions::MentionFinder;
+use resolver::{LinkResolver, Resolution};
+use scanner::{search_vault_files, ScanConfig, VaultFiles, VaultScanner};
 
-// Search the target folder and all subfolders (recursively) for Markdown files
-fn search_markdown_files(folder_path: &Path) -> Vec<(PathBuf, String)> {
-    let mut file_list = Vec::new();
+pub mod cache;
+pub mod canvas;
+pub mod fields;
+pub mod format;
+pub mod frontmatter;
+pub mod links;
+pub mod markdown;
+pub mod mentions;
+pub mod resolver;
+pub mod roam;
+pub mod scanner;
+pub mod stats;
+pub mod tags;
+pub mod watcher;
 
-    fn recursive_file_search(folder_path: &Path, file_list: &mut Vec<(PathBuf, String)>) {
-        for entry in fs::read_dir(folder_path).unwrap() {
-            if let Ok(entry) = entry {
-                let file_path = entry.path();
-                if file_path.is_file() && file_path.extension() == Some(&std::ffi::OsStr::new("md"))
-                {
-                    let file_path_buf = file_path.to_path_buf();
-                    let file_title = file_path
-                        .file_stem()
-                        .and_then(|os_str| os_str.to_str())
-                        .unwrap()
-                        .to_string();
-                    file_list.push((file_path_buf, file_title));
-                } else if file_path.is_dir() {
-                    recursive_file_search(&file_path, file_list);
+/// Describes why a vault could not be parsed at all
+#[derive(Debug)]
+pub enum VaultError {
+    /// The vault path does not point to a directory
+    NotADirectory(PathBuf),
+    /// The vault directory could not be read
+    Unreadable(PathBuf, io::Error),
+    /// The vault directory could not be watched for changes
+    Unwatchable(PathBuf, notify::Error),
+    /// The exported knowledge base could not be parsed
+    InvalidExport(PathBuf, String),
+}
+
+impl fmt::Display for VaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VaultError::NotADirectory(path) => {
+                write!(f, "{} is not a directory", path.display())
+            }
+            VaultError::Unreadable(path, error) => {
+                write!(f, "Could not read {}: {}", path.display(), error)
+            }
+            VaultError::InvalidExport(path, message) => {
+                write!(f, "Could not import {}: {}", path.display(), message)
+            }
+            VaultError::Unwatchable(path, error) => {
+                write!(
+                    f,
+                    "Could not watch {} for changes: {}",
+                    path.display(),
+                    error
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+/// Describes a problem encountered while parsing a single file of a vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Diagnostic {
+    /// The file or folder was not parsed
+    SkippedFile { file: PathBuf, reason: String },
+    /// The file or folder could not be read
+    UnreadableFile { file: PathBuf, message: String },
+    /// The file contains invalid UTF-8, which was replaced before parsing
+    InvalidUtf8 { file: PathBuf },
+    /// The frontmatter block of the file is not valid YAML
+    InvalidFrontmatter { file: PathBuf, message: String },
+    /// The canvas file is not valid JSON Canvas
+    InvalidCanvas { file: PathBuf, message: String },
+    /// A link in the file matches no page, so an empty page was created
+    UnresolvedLink { file: PathBuf, target: String },
+    /// A link in the file matches several pages, of which the first candidate was chosen
+    AmbiguousLink {
+        file: PathBuf,
+        target: String,
+        candidates: Vec<PathBuf>,
+    },
+}
+
+impl Diagnostic {
+    /// Returns a short description of the kind of problem
+    pub fn category(&self) -> &'static str {
+        match self {
+            Diagnostic::SkippedFile { .. } => "Skipped files",
+            Diagnostic::UnreadableFile { .. } => "Unreadable files",
+            Diagnostic::InvalidUtf8 { .. } => "Invalid UTF-8",
+            Diagnostic::InvalidFrontmatter { .. } => "Frontmatter errors",
+            Diagnostic::InvalidCanvas { .. } => "Canvas errors",
+            Diagnostic::UnresolvedLink { .. } => "Unresolved links",
+            Diagnostic::AmbiguousLink { .. } => "Ambiguous links",
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::SkippedFile { file, reason } => {
+                write!(f, "Skipped {}: {}", file.display(), reason)
+            }
+            Diagnostic::UnreadableFile { file, message } => {
+                write!(f, "Could not read {}: {}", file.display(), message)
+            }
+            Diagnostic::InvalidUtf8 { file } => {
+                write!(f, "Invalid UTF-8 in {}", file.display())
+            }
+            Diagnostic::InvalidFrontmatter { file, message } => {
+                write!(f, "Invalid frontmatter in {}: {}", file.display(), message)
+            }
+            Diagnostic::InvalidCanvas { file, message } => {
+                write!(f, "Invalid canvas {}: {}", file.display(), message)
+            }
+            Diagnostic::UnresolvedLink { file, target } => {
+                write!(f, "Unresolved link [[{}]] in {}", target, file.display())
+            }
+            Diagnostic::AmbiguousLink {
+                file,
+                target,
+                candidates,
+            } => {
+                let candidates: Vec<String> = candidates
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                write!(
+                    f,
+                    "Ambiguous link [[{}]] in {}, matching {}",
+                    target,
+                    file.display(),
+                    candidates.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// This struct describes how far the parsing of a vault has progressed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Progress {
+    /// Number of files parsed so far
+    pub parsed_files: usize,
+    /// Number of files to parse
+    pub total_files: usize,
+}
+
+/// This struct summarizes the problems encountered while parsing a vault.
+#[derive(Debug, Clone, Default)]
+pub struct ParseReport {
+    /// Number of Markdown files which were parsed
+    pub parsed_files: usize,
+    /// Problems with individual files
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files found when scanning the vault, kept so that updates only scan changed files
+    pub files: VaultFiles,
+}
+
+impl ParseReport {
+    /// Returns the diagnostics grouped by their [category](Diagnostic::category)
+    pub fn categories(&self) -> BTreeMap<&'static str, Vec<&Diagnostic>> {
+        let mut categories: BTreeMap<&'static str, Vec<&Diagnostic>> = BTreeMap::new();
+        for diagnostic in &self.diagnostics {
+            categories
+                .entry(diagnostic.category())
+                .or_default()
+                .push(diagnostic);
+        }
+        categories
+    }
+}
+
+// Decode the contents of a Markdown file, replacing invalid UTF-8 sequences
+fn decode_markdown_file(bytes: Vec<u8>, relative_path: &Path) -> (String, Option<Diagnostic>) {
+    match String::from_utf8(bytes) {
+        Ok(contents) => (contents, None),
+        Err(error) => (
+            String::from_utf8_lossy(error.as_bytes()).to_string(),
+            Some(Diagnostic::InvalidUtf8 {
+                file: relative_path.to_path_buf(),
+            }),
+        ),
+    }
+}
+
+// Search a Markdown file for frontmatter properties
+fn search_properties(contents: &str) -> Result<BTreeMap<String, PropertyValue>, String> {
+    match frontmatter::split_frontmatter(contents) {
+        (Some(yaml), _) => frontmatter::parse_frontmatter(yaml),
+        (None, _) => Ok(BTreeMap::new()),
+    }
+}
+
+// Search the body of a Markdown file for inline tags, skipping links (`[[#Heading]]`)
+fn search_tags(contents: &str) -> Vec<String> {
+    let (_, body) = frontmatter::split_frontmatter(contents);
+    tags::search_inline_tags(&links::mask_links(body))
+}
+
+// Resolve the links of all pages to the paths of the pages they refer to (see [LinkResolver]),
+// and add empty pages for links to pages which do not exist (and pages for linked URLs)
+fn resolve_links(
+    pages: &mut Vec<Page>,
+    attachment_folder: Option<&str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let resolver = LinkResolver::new(pages, attachment_folder);
+    let mut new_pages: HashMap<String, Page> = HashMap::new();
+
+    for page in pages.iter_mut() {
+        let mut resolve = |target: &str| -> PathBuf {
+            if links::is_url(target) {
+                return new_pages
+                    .entry(target.to_string())
+                    .or_insert_with(|| Page {
+                        title: target.to_string(),
+                        path: PathBuf::from(target),
+                        kind: PageKind::Url,
+                        ..Default::default()
+                    })
+                    .path
+                    .clone();
+            }
+
+            match resolver.resolve(target, &page.path) {
+                Resolution::Resolved(path) => path,
+                Resolution::Ambiguous(path, candidates) => {
+                    diagnostics.push(Diagnostic::AmbiguousLink {
+                        file: page.path.clone(),
+                        target: target.to_string(),
+                        candidates,
+                    });
+                    path
+                }
+                Resolution::Unresolved => {
+                    diagnostics.push(Diagnostic::UnresolvedLink {
+                        file: page.path.clone(),
+                        target: target.to_string(),
+                    });
+
+                    let target = target.trim().trim_start_matches('/');
+                    let target = target.strip_suffix(".md").unwrap_or(target);
+
+                    new_pages
+                        .entry(target.to_lowercase())
+                        .or_insert_with(|| Page {
+                            title: target.rsplit('/').next().unwrap_or(target).to_string(),
+                            path: PathBuf::from(format!("{}.md", target)),
+                            empty: true,
+                            ..Default::default()
+                        })
+                        .path
+                        .clone()
                 }
             }
+        };
+
+        let mut links = std::mem::take(&mut page.links);
+        for link in links.iter_mut() {
+            link.path = resolve(&link.target);
+            link.source_path = link.source.as_deref().map(&mut resolve);
         }
+        page.links = links;
     }
 
-    recursive_file_search(folder_path, &mut file_list);
-    file_list
+    pages.extend(new_pages.into_values());
 }
 
-// Search a Markdown file for metadata tags
-fn search_tags(file: &Path) -> Vec<String> {
-    let mut contents = String::new();
-    fs::File::open(file)
-        .and_then(|mut f| f.read_to_string(&mut contents))
-        .unwrap_or_default();
+// Parse a single Markdown file into a page, reusing the cached page if the file is unchanged
+fn parse_file(
+    file: &Path,
+    relative_path: &Path,
+    title: &str,
+    config: &ScanConfig,
+    cache: &ParseCache,
+) -> Result<CacheEntry, Diagnostic> {
+    let unreadable = |error: io::Error| Diagnostic::UnreadableFile {
+        file: relative_path.to_path_buf(),
+        message: error.to_string(),
+    };
 
-    let tag_pattern = Regex::new(r"---\n\s*(?:tags: |tag: )(.*?)\n---").unwrap();
-    if let Some(captures) = tag_pattern.captures(&contents) {
-        let tags_string = captures.get(1).unwrap().as_str().trim();
-        let tags: Vec<String> = tags_string
-            .split(',')
-            .map(|tag| tag.trim().to_string())
-            .collect();
-        return tags;
+    let metadata = fs::metadata(file).map_err(unreadable)?;
+    let state = FileState::new(&metadata);
+    if let Some(entry) = cache.unchanged(relative_path, &state) {
+        return Ok(entry.clone());
+    }
+
+    let bytes = fs::read(file).map_err(unreadable)?;
+    let hash = cache::hash_contents(&bytes);
+    if let Some(entry) = cache.same_contents(relative_path, hash) {
+        let mut entry = CacheEntry {
+            state,
+            ..entry.clone()
+        };
+        stats::add_file_stats(&mut entry.page.stats, &metadata);
+        return Ok(entry);
+    }
+
+    let (contents, diagnostic) = decode_markdown_file(bytes, relative_path);
+    let mut diagnostics: Vec<Diagnostic> = diagnostic.into_iter().collect();
+    let mut page = parse_markdown(
+        &contents,
+        relative_path,
+        title,
+        config.format,
+        &mut diagnostics,
+    );
+    stats::add_file_stats(&mut page.stats, &metadata);
+    // The text is not kept (nor cached) unless it is indexed or searched for mentions
+    if !config.keeps_content() {
+        page.content = None;
     }
-    Vec::new()
+
+    Ok(CacheEntry {
+        state,
+        hash,
+        page,
+        diagnostics,
+    })
 }
 
-// Search a Markdown file for links of the form [[Linked page |...]] or [[Linked page]]
-fn search_links(file: &Path) -> Vec<String> {
-    let mut contents = String::new();
-    fs::File::open(file)
-        .and_then(|mut f| f.read_to_string(&mut contents))
-        .unwrap_or_default();
+// Parse the contents of a Markdown file into a page, without file statistics
+fn parse_markdown(
+    contents: &str,
+    relative_path: &Path,
+    title: &str,
+    format: VaultFormat,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Page {
+    let source_dir = relative_path.parent().unwrap_or(Path::new(""));
+    let mut properties = search_properties(contents).unwrap_or_else(|message| {
+        diagnostics.push(Diagnostic::InvalidFrontmatter {
+            file: relative_path.to_path_buf(),
+            message,
+        });
+        BTreeMap::new()
+    });
+    // Links and tags are only searched in prose, not in code, comments or math
+    let prose = markdown::mask_non_prose(contents);
 
-    let link_pattern = Regex::new(r"\[\[(.*?)(?:\s*\|.*?)?\]\]").unwrap();
-    let links: Vec<String> = link_pattern
-        .captures_iter(&contents)
-        .map(|capture| capture.get(1).map_or("", |m| m.as_str()).trim().to_string())
-        .filter(|link| !link.is_empty())
-        .collect();
-    links
-        .into_iter()
-        .collect::<HashSet<_>>()
+    let mut tags = frontmatter::frontmatter_tags(&properties);
+    for tag in search_tags(&prose) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            tags.push(tag)
+        }
+    }
+
+    // Links in the frontmatter are only taken from the values of properties
+    let (yaml, _) = frontmatter::split_frontmatter(contents);
+    let frontmatter_lines = yaml.map_or(0, |yaml| yaml.lines().count() + 2);
+    let yaml = yaml.unwrap_or("");
+    let mut links = frontmatter::frontmatter_links(yaml, &properties);
+
+    let fields: Vec<_> = fields::search_inline_fields(&prose)
         .into_iter()
-        .collect()
+        .filter(|field| field.line > frontmatter_lines)
+        .collect();
+    let mut body_links = links::search_links(&prose, source_dir);
+    body_links.extend(format.search_links(&prose, source_dir));
+    body_links.sort_by_key(|link| (link.line, link.column));
+    for mut link in body_links {
+        if link.line <= frontmatter_lines {
+            continue;
+        }
+        link.property = fields
+            .iter()
+            .find(|field| field.contains(link.line, link.column))
+            .map(|field| field.key.clone());
+        links.push(link);
+    }
+    fields::merge_inline_fields(&mut properties, &fields);
+    add_link_contexts(&prose, &mut links);
+
+    Page {
+        title: title.to_string(),
+        path: relative_path.to_path_buf(),
+        tags,
+        properties,
+        empty: false,
+        kind: PageKind::Note,
+        stats: stats::content_stats(contents),
+        content: Some(contents.to_string()),
+        links,
+    }
+}
+
+// Fill in the sentence around each link, taken from the (masked) Markdown it was found in
+fn add_link_contexts(prose: &str, links: &mut [Link]) {
+    let line_index = LineIndex::new(prose);
+    for link in links.iter_mut().filter(|link| link.context.is_none()) {
+        let offset = line_index.offset(link.line, link.column);
+        link.context = Some(markdown::surrounding_sentence(prose, offset..offset));
+    }
 }
 
 /// Extract all markdown files from a directory
-pub fn extract_pages(vault_dir: &Path) -> Vec<Page> {
-    let mut pages = Vec::new();
-    let md_files = search_markdown_files(vault_dir);
-
-    for (file, title) in &md_files {
-        let tags = search_tags(file);
-        let links = search_links(file);
-
-        pages.push(Page {
-            title: title.to_string(),
-            tags,
-            empty: false,
-            links: links.clone(),
-        });
+///
+/// The files to parse are selected according to the [ScanConfig].
+/// Files which can only be partially parsed are reported in the [ParseReport].
+/// A Roam Research JSON export (a single file) is imported with [roam::import_roam_export].
+pub fn extract_pages(
+    vault_dir: &Path,
+    config: &ScanConfig,
+) -> Result<(Vec<Page>, ParseReport), VaultError> {
+    extract_pages_with_progress(vault_dir, config, |_| {})
+}
+
+/// Extract all markdown files from a directory, reporting the [Progress] after every parsed file
+///
+/// Files are parsed in parallel, so `progress` may be called from several threads.
+pub fn extract_pages_with_progress(
+    vault_dir: &Path,
+    config: &ScanConfig,
+    progress: impl Fn(Progress) + Sync,
+) -> Result<(Vec<Page>, ParseReport), VaultError> {
+    if roam::is_roam_export(vault_dir) {
+        return roam::import_roam_export(vault_dir);
+    }
+    if !vault_dir.is_dir() {
+        return Err(VaultError::NotADirectory(vault_dir.to_path_buf()));
+    }
+    fs::read_dir(vault_dir)
+        .map_err(|error| VaultError::Unreadable(vault_dir.to_path_buf(), error))?;
+
+    let mut report = ParseReport::default();
+    let files = search_vault_files(vault_dir, config, &mut report.diagnostics);
+    let md_files = &files.markdown;
+    let mut cache = if config.use_cache {
+        ParseCache::load(vault_dir, config)
+    } else {
+        ParseCache::default()
+    };
+
+    let total_files = md_files.len();
+    let parsed_files = AtomicUsize::new(0);
+    progress(Progress {
+        parsed_files: 0,
+        total_files,
+    });
+
+    let results: Vec<Result<CacheEntry, Diagnostic>> = md_files
+        .par_iter()
+        .map(|(file, title)| {
+            let relative_path = file.strip_prefix(vault_dir).unwrap_or(file);
+            let result = parse_file(file, relative_path, title, config, &cache);
+
+            progress(Progress {
+                parsed_files: parsed_files.fetch_add(1, Ordering::Relaxed) + 1,
+                total_files,
+            });
+            result
+        })
+        .collect();
+
+    // Only keep the pages of files which still exist in the cache
+    cache.clear();
+    let pages = collect_results(results, &mut report, &mut cache);
+    cache.save();
+    let mut pages = pages;
+    pages.extend(file_pages(
+        vault_dir,
+        config,
+        &files,
+        &HashMap::new(),
+        &mut report,
+    ));
+    report.files = files;
+    let pages = resolve_pages(config, pages, &mut report);
+
+    Ok((pages, report))
+}
+
+/// Updates the pages of a vault after some of its files changed, re-parsing only the changed files.
+///
+/// `changed_paths` are the vault-relative paths of the created, modified, renamed and deleted
+/// files and folders. Only these are scanned again, the other files are taken from the `report`
+/// of the previous load or update. The links of all pages are resolved again, since new and
+/// renamed pages may be the target of links which were previously unresolved.
+pub fn update_pages(
+    vault_dir: &Path,
+    config: &ScanConfig,
+    pages: Vec<Page>,
+    report: &ParseReport,
+    changed_paths: &[PathBuf],
+) -> Result<(Vec<Page>, ParseReport), VaultError> {
+    if !vault_dir.is_dir() {
+        return Err(VaultError::NotADirectory(vault_dir.to_path_buf()));
+    }
+
+    let is_changed = |path: &Path| {
+        changed_paths
+            .iter()
+            .any(|changed| path.starts_with(changed))
+    };
+    let mut new_report = ParseReport::default();
+
+    // Keep the problems found when the unchanged files were scanned and parsed.
+    // Links are resolved anew, so their problems are found again.
+    new_report.diagnostics.extend(
+        report
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| match diagnostic {
+                Diagnostic::SkippedFile { file, .. }
+                | Diagnostic::UnreadableFile { file, .. }
+                | Diagnostic::InvalidUtf8 { file }
+                | Diagnostic::InvalidFrontmatter { file, .. }
+                | Diagnostic::InvalidCanvas { file, .. } => !is_changed(file),
+                Diagnostic::UnresolvedLink { .. } | Diagnostic::AmbiguousLink { .. } => false,
+            })
+            .cloned(),
+    );
+
+    let mut files = report.files.clone();
+    files.retain(|file| !is_changed(file.strip_prefix(vault_dir).unwrap_or(file)));
+    let changed_files = VaultScanner::new(vault_dir, config, &mut new_report.diagnostics)
+        .search_paths(changed_paths, &mut new_report.diagnostics);
+    files.extend(changed_files);
+
+    // Notes are parsed again if their text is now kept, but was not before
+    let lacks_content = |page: &Page| {
+        config.keeps_content() && page.kind == PageKind::Note && page.content.is_none()
+    };
+    let old_pages: HashMap<PathBuf, Page> = pages
+        .into_iter()
+        .filter(|page| !page.empty && !is_changed(&page.path) && !lacks_content(page))
+        .map(|page| (page.path.clone(), page))
+        .collect();
+
+    let results: Vec<Result<CacheEntry, Diagnostic>> = files
+        .markdown
+        .par_iter()
+        .map(|(file, title)| {
+            let relative_path = file.strip_prefix(vault_dir).unwrap_or(file);
+            match old_pages.get(relative_path) {
+                Some(page) => Ok(CacheEntry {
+                    page: page.clone(),
+                    ..Default::default()
+                }),
+                None => parse_file(file, relative_path, title, config, &ParseCache::default()),
+            }
+        })
+        .collect();
+
+    // Changed files are cached the next time the whole vault is loaded
+    let mut pages = collect_results(results, &mut new_report, &mut ParseCache::default());
+    pages.extend(file_pages(
+        vault_dir,
+        config,
+        &files,
+        &old_pages,
+        &mut new_report,
+    ));
+    new_report.files = files;
+    let pages = resolve_pages(config, pages, &mut new_report);
+
+    Ok((pages, new_report))
+}
+
+// Create the pages of the attachments and canvases of a vault. The pages in `old_pages`
+// are reused, rather than reading their files again.
+fn file_pages(
+    vault_dir: &Path,
+    config: &ScanConfig,
+    files: &VaultFiles,
+    old_pages: &HashMap<PathBuf, Page>,
+    report: &mut ParseReport,
+) -> Vec<Page> {
+    let mut pages: Vec<Page> = files
+        .attachments
+        .iter()
+        .map(|file| {
+            let relative_path = file.strip_prefix(vault_dir).unwrap_or(file);
+            if let Some(page) = old_pages.get(relative_path) {
+                return page.clone();
+            }
+            let mut stats = PageStats::default();
+            if let Ok(metadata) = fs::metadata(file) {
+                stats::add_file_stats(&mut stats, &metadata);
+            }
+
+            Page {
+                title: relative_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: relative_path.to_path_buf(),
+                kind: PageKind::Attachment {
+                    file_type: relative_path
+                        .extension()
+                        .map(|extension| extension.to_string_lossy().to_lowercase())
+                        .unwrap_or_default(),
+                    size: stats.size,
+                },
+                stats,
+                ..Default::default()
+            }
+        })
+        .collect();
 
-        for page in &links {
-            if !md_files.iter().any(|(_, t)| t == page) {
+    for file in &files.canvases {
+        let relative_path = file.strip_prefix(vault_dir).unwrap_or(file);
+        if let Some(page) = old_pages.get(relative_path) {
+            pages.push(page.clone());
+            report.parsed_files += 1;
+            continue;
+        }
+        let source_dir = relative_path.parent().unwrap_or(Path::new(""));
+        let links = fs::read_to_string(file)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                canvas::search_canvas_links(&contents, source_dir, config.include_canvas_edges)
+            });
+
+        let mut stats = PageStats::default();
+        if let Ok(metadata) = fs::metadata(file) {
+            stats::add_file_stats(&mut stats, &metadata);
+        }
+
+        match links {
+            Ok(links) => {
                 pages.push(Page {
-                    title: page.to_string(),
-                    tags: Vec::new(),
-                    empty: true,
-                    links: Vec::new(),
+                    title: relative_path
+                        .file_stem()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    path: relative_path.to_path_buf(),
+                    kind: PageKind::Canvas,
+                    stats,
+                    links,
+                    ..Default::default()
                 });
+                report.parsed_files += 1;
             }
+            Err(message) => report.diagnostics.push(Diagnostic::InvalidCanvas {
+                file: relative_path.to_path_buf(),
+                message,
+            }),
+        }
+    }
+
+    pages
+}
+
+// Search the pages of a vault for unlinked mentions (if enabled) and resolve all links.
+// Attachments are dropped afterwards, unless they are to be included.
+fn resolve_pages(config: &ScanConfig, mut pages: Vec<Page>, report: &mut ParseReport) -> Vec<Page> {
+    config.format.link_pages(&mut pages);
+
+    // Mentions depend on the titles of all pages, so they are searched anew on every update
+    for page in pages.iter_mut() {
+        page.links.retain(|link| link.kind != LinkKind::Mention);
+    }
+    // The text of notes was kept when they were parsed, so files are not read again
+    if config.include_unlinked_mentions {
+        let finder = MentionFinder::new(&pages);
+        let mentions: Vec<Vec<Link>> = pages
+            .par_iter()
+            .map(|page| match &page.content {
+                Some(contents) if page.kind == PageKind::Note => {
+                    finder.search(contents, &page.path)
+                }
+                _ => Vec::new(),
+            })
+            .collect();
+        for (page, links) in pages.iter_mut().zip(mentions) {
+            page.links.extend(links);
         }
     }
 
+    resolve_links(
+        &mut pages,
+        report.files.attachment_folder.as_deref(),
+        &mut report.diagnostics,
+    );
+
+    if !config.include_attachments {
+        pages.retain(|page| !matches!(page.kind, PageKind::Attachment { .. }));
+    }
+    pages
+}
+
+// Collect parsed pages, adding their problems to the report and the pages to the cache
+fn collect_results(
+    results: Vec<Result<CacheEntry, Diagnostic>>,
+    report: &mut ParseReport,
+    cache: &mut ParseCache,
+) -> Vec<Page> {
+    let mut pages = Vec::with_capacity(results.len());
+    for result in results {
+        match result {
+            Ok(entry) => {
+                pages.push(entry.page.clone());
+                report.parsed_files += 1;
+                report.diagnostics.extend(entry.diagnostics.iter().cloned());
+                cache.insert(entry);
+            }
+            Err(diagnostic) => report.diagnostics.push(diagnostic),
+        }
+    }
     pages
 }
 
 /// Reads a vector of Page structs and converts it to a petgraph instance
-pub fn pages_to_graph(pages: Vec<Page>) -> Graph<Page, ()> {
+pub fn pages_to_graph(pages: Vec<Page>) -> Graph<Page, Edge> {
     // Create a directed graph
-    let mut graph: Graph<Page, ()> = Graph::new();
+    let mut graph: Graph<Page, Edge> = Graph::new();
 
-    // Create a hashmap to quickly find nodes (pages) by their title
-    let mut title_to_node = HashMap::new();
+    // Create a hashmap to quickly find nodes (pages) by their path
+    let mut path_to_node = HashMap::new();
 
     // Add nodes (pages) to the graph and populate the hashmap
     for page in &pages {
-        let page_index = *title_to_node.entry(page.title.clone()).or_insert_with(|| {
-            let new_page_index = graph.add_node(page.clone());
-            new_page_index
-        });
-        title_to_node.insert(page.title.clone(), page_index);
+        path_to_node
+            .entry(page.path.clone())
+            .or_insert_with(|| graph.add_node(page.clone()));
     }
 
-    // Add edges (links) to the graph
-    for page in pages.iter() {
-        let source_node_index = title_to_node.get(&page.title).expect("Node not found");
-        for linked_page_title in page.links.iter() {
-            let target_node_index = title_to_node
-                .get(linked_page_title)
-                .expect("Node not found");
-            graph.add_edge(*source_node_index, *target_node_index, ());
+    add_link_edges(&mut graph, &path_to_node);
+
+    graph
+}
+
+/// Adds an edge to the graph for every link between the pages of its nodes.
+///
+/// `path_to_node` maps the path of every page to its node.
+pub(crate) fn add_link_edges(
+    graph: &mut Graph<Page, Edge>,
+    path_to_node: &HashMap<PathBuf, NodeIndex>,
+) {
+    for page_node in graph.node_indices() {
+        let links = graph[page_node].links.clone();
+
+        for link in links.iter() {
+            // Canvas relations start from another page than the canvas itself
+            let source = match &link.source_path {
+                Some(source_path) => match path_to_node.get(source_path) {
+                    Some(&source) => source,
+                    None => continue,
+                },
+                None => page_node,
+            };
+            let Some(&target) = path_to_node.get(&link.path) else {
+                continue;
+            };
+
+            // Links to headings or blocks within the same page are not drawn, and
+            // links of the same kind to the same page share a single edge
+            if target == source {
+                continue;
+            }
+            let existing = graph
+                .edges_connecting(source, target)
+                .find(|edge| edge.weight().kind == link.kind)
+                .map(|edge| edge.id());
+            match existing {
+                Some(edge) => graph[edge].add_link(link),
+                None => {
+                    graph.add_edge(source, target, Edge::new(link));
+                }
+            }
         }
     }
+}
 
-    graph
+/// Converts an Obsidian vault to a petgraph instance, along with a [ParseReport] of its files
+pub fn vault_to_graph(
+    vault_dir: &Path,
+    config: &ScanConfig,
+) -> Result<(Graph<Page, Edge>, ParseReport), VaultError> {
+    let (pages, report) = extract_pages(vault_dir, config)?;
+
+    Ok((pages_to_graph(pages), report))
 }
 
-/// Converts an Obsidian vault to a petgraph instance
-pub fn vault_to_graph(vault_dir: &Path) -> Graph<Page, ()> {
-    pages_to_graph(extract_pages(vault_dir))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdgeFilter;
+    use scanner::search_markdown_files;
+
+    // Create a vault with the given files in a fresh temporary directory
+    fn create_testing_vault(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let vault_dir =
+            std::env::temp_dir().join(format!("obsidian_graph_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&vault_dir);
+
+        for (path, contents) in files {
+            let file = vault_dir.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, contents).unwrap();
+        }
+
+        vault_dir
+    }
+
+    #[test]
+    fn parse_report() {
+        let vault_dir = create_testing_vault(
+            "parse_report",
+            &[
+                ("Note.md", b"---\ntags: [a\n---\n[[Missing]]"),
+                ("folder/Binary.md", b"Text \xff\xfe [[Note]]"),
+            ],
+        );
+
+        let (pages, report) = extract_pages(&vault_dir, &ScanConfig::default()).unwrap();
+        let categories = report.categories();
+
+        assert_eq!(report.parsed_files, 2);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(categories["Frontmatter errors"].len(), 1);
+        assert_eq!(categories["Invalid UTF-8"].len(), 1);
+        assert_eq!(categories["Unresolved links"].len(), 1);
+        assert!(matches!(
+            extract_pages(&vault_dir.join("Note.md"), &ScanConfig::default()),
+            Err(VaultError::NotADirectory(_))
+        ));
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn parsing_progress() {
+        let files: Vec<(String, Vec<u8>)> = (0..20)
+            .map(|i| {
+                (
+                    format!("Note {}.md", i),
+                    format!("[[Note {}]]", i + 1).into_bytes(),
+                )
+            })
+            .collect();
+        let files: Vec<(&str, &[u8])> = files
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice()))
+            .collect();
+        let vault_dir = create_testing_vault("parsing_progress", &files);
+
+        let updates = std::sync::Mutex::new(Vec::new());
+        let (pages, report) =
+            extract_pages_with_progress(&vault_dir, &ScanConfig::default(), |progress| {
+                updates.lock().unwrap().push(progress)
+            })
+            .unwrap();
+        let updates = updates.into_inner().unwrap();
+
+        assert_eq!(report.parsed_files, 20);
+        assert_eq!(pages.len(), 21);
+        assert_eq!(updates.len(), 21);
+        assert!(updates.iter().all(|progress| progress.total_files == 20));
+        assert_eq!(
+            updates.iter().map(|progress| progress.parsed_files).max(),
+            Some(20)
+        );
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn incremental_update() {
+        let vault_dir = create_testing_vault(
+            "incremental_update",
+            &[
+                ("Note.md", b"[[New]]"),
+                ("Old.md", b"---\ntags: [a\n---\n"),
+                ("Other.md", b"---\ntags: [a\n---\n"),
+            ],
+        );
+        let (pages, report) = extract_pages(&vault_dir, &ScanConfig::default()).unwrap();
+        assert!(pages.iter().any(|page| page.empty));
+
+        fs::rename(vault_dir.join("Old.md"), vault_dir.join("New.md")).unwrap();
+        // Files which are not reported as changed are not scanned
+        fs::write(vault_dir.join("Unseen.md"), "").unwrap();
+        let (pages, report) = update_pages(
+            &vault_dir,
+            &ScanConfig::default(),
+            pages,
+            &report,
+            &[PathBuf::from("Old.md"), PathBuf::from("New.md")],
+        )
+        .unwrap();
+        let categories = report.categories();
+
+        // The renamed page resolves the previously unresolved link
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().all(|page| !page.empty));
+        assert!(!categories.contains_key("Unresolved links"));
+        assert_eq!(categories["Frontmatter errors"].len(), 2);
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn parse_cache() {
+        let vault_dir = create_testing_vault(
+            "parse_cache",
+            &[(".obsidian/app.json", b"{}"), ("Note.md", b"[[A]]")],
+        );
+        let config = ScanConfig {
+            use_cache: true,
+            ..Default::default()
+        };
+        let targets = |pages: &[Page]| -> Vec<String> {
+            let note = pages.iter().find(|page| !page.empty).unwrap();
+            note.links()
+                .iter()
+                .map(|link| link.target.clone())
+                .collect()
+        };
+
+        extract_pages(&vault_dir, &config).unwrap();
+        let cache_file = cache::cache_path(&vault_dir).unwrap();
+        assert!(cache_file.is_file());
+        assert!(!vault_dir
+            .join(".obsidian")
+            .read_dir()
+            .unwrap()
+            .any(|entry| entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .contains("cache")));
+
+        // A file with unchanged modification time and size is not parsed again
+        let note_path = vault_dir.join("Note.md");
+        let modified = fs::metadata(&note_path).unwrap().modified().unwrap();
+        fs::write(&note_path, "[[B]]").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&note_path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let (pages, _) = extract_pages(&vault_dir, &config).unwrap();
+        assert_eq!(targets(&pages), vec!["A"]);
+
+        fs::write(&note_path, "[[Changed]]").unwrap();
+        let (pages, _) = extract_pages(&vault_dir, &config).unwrap();
+        assert_eq!(targets(&pages), vec!["Changed"]);
+
+        fs::remove_file(cache_file).unwrap();
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn attachments() {
+        let vault_dir = create_testing_vault(
+            "attachments",
+            &[
+                ("Note.md", b"![[diagram.png]] [doc](files/Doc.PDF)"),
+                ("images/diagram.png", b"png"),
+                ("files/Doc.PDF", b"%PDF"),
+            ],
+        );
+
+        // Links to attachments do not create empty pages, even if attachments are left out
+        let (pages, _) = extract_pages(&vault_dir, &ScanConfig::default()).unwrap();
+        assert_eq!(pages.len(), 1);
+
+        let config = ScanConfig {
+            include_attachments: true,
+            ..Default::default()
+        };
+        let (pages, _) = extract_pages(&vault_dir, &config).unwrap();
+        let graph = pages_to_graph(pages);
+        let attachment = graph
+            .node_weights()
+            .find(|page| page.title == "Doc.PDF")
+            .unwrap();
+
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(
+            attachment.kind,
+            PageKind::Attachment {
+                file_type: "pdf".to_string(),
+                size: 4
+            }
+        );
+        let matches = |filter: &str| {
+            let filter = crate::filtering::Filter::parse(filter).unwrap();
+            crate::filtering::evaluate_filter(&filter, attachment)
+        };
+        assert!(matches("kind:attachment"));
+        assert!(matches("kind:pdf"));
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn attachment_folder_setting() {
+        let vault_dir = create_testing_vault(
+            "attachment_folder_setting",
+            &[
+                (
+                    ".obsidian/app.json",
+                    br#"{"attachmentFolderPath": "assets"}"#,
+                ),
+                ("notes/Note.md", b"![[diagram.png]]"),
+                ("archive/diagram.png", b"png"),
+                ("assets/diagram.png", b"png"),
+            ],
+        );
+
+        // Of several attachments with the same name, the one in the attachment folder is linked
+        let (pages, report) = extract_pages(&vault_dir, &ScanConfig::default()).unwrap();
+        assert_eq!(
+            pages[0].links()[0].path,
+            PathBuf::from("assets/diagram.png")
+        );
+        assert!(report.diagnostics.is_empty());
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn canvas_files() {
+        let vault_dir = create_testing_vault(
+            "canvas_files",
+            &[
+                ("A.md", b""),
+                ("B.md", b""),
+                (
+                    "Plan.canvas",
+                    br#"{"nodes": [
+                        {"id": "1", "type": "file", "file": "A.md"},
+                        {"id": "2", "type": "file", "file": "B.md"},
+                        {"id": "3", "type": "link", "url": "https://example.com"}
+                    ], "edges": [{"id": "e", "fromNode": "1", "toNode": "2", "label": "blocks"}]}"#,
+                ),
+                ("Broken.canvas", b"{"),
+            ],
+        );
+        let config = ScanConfig {
+            include_canvas_edges: true,
+            ..Default::default()
+        };
+
+        let (graph, report) = vault_to_graph(&vault_dir, &config).unwrap();
+        let node = |path: &str| {
+            graph
+                .node_indices()
+                .find(|index| graph[*index].path == Path::new(path))
+                .unwrap()
+        };
+
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph[node("Plan.canvas")].kind, PageKind::Canvas);
+        assert_eq!(graph[node("https://example.com")].kind, PageKind::Url);
+        assert_eq!(
+            graph
+                .edges_connecting(node("Plan.canvas"), node("A.md"))
+                .count(),
+            1
+        );
+        assert_eq!(
+            graph
+                .edges_connecting(node("A.md"), node("B.md"))
+                .map(|edge| edge.weight().kind)
+                .collect::<Vec<_>>(),
+            vec![LinkKind::Relation]
+        );
+        assert_eq!(report.categories()["Canvas errors"].len(), 1);
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn property_links() {
+        let vault_dir = create_testing_vault(
+            "property_links",
+            &[
+                (
+                    "Task.md",
+                    b"---\nup: \"[[Index]]\"\nstatus: open\n---\nparent:: [[Project X]]\nSee [[Index]] [due:: 2023-07-01]\n",
+                ),
+                ("Index.md", b""),
+                ("Project X.md", b""),
+            ],
+        );
+
+        let (pages, _) = extract_pages(&vault_dir, &ScanConfig::default()).unwrap();
+        let task = pages
+            .iter()
+            .find(|page| page.path == Path::new("Task.md"))
+            .unwrap();
+        let links: Vec<(&str, Option<&str>, usize)> = task
+            .links()
+            .iter()
+            .map(|link| (link.target.as_str(), link.property.as_deref(), link.line))
+            .collect();
+
+        assert_eq!(
+            links,
+            vec![
+                ("Index", Some("up"), 2),
+                ("Project X", Some("parent"), 5),
+                ("Index", None, 6)
+            ]
+        );
+        assert_eq!(
+            task.properties.get("parent"),
+            Some(&PropertyValue::Link("Project X".to_string()))
+        );
+        assert!(matches!(
+            task.properties.get("due"),
+            Some(PropertyValue::Date(_))
+        ));
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn edge_weights() {
+        let vault_dir = create_testing_vault(
+            "edge_weights",
+            &[
+                (
+                    "A.md",
+                    b"up:: [[B]]\nSee [[B#Intro|the intro]]\n![[B]] and [[B#^def]]\n",
+                ),
+                ("B.md", b""),
+            ],
+        );
+
+        let (graph, _) = vault_to_graph(&vault_dir, &ScanConfig::default()).unwrap();
+        let mut edges: Vec<&Edge> = graph.edge_weights().collect();
+        edges.sort_by_key(|edge| edge.count);
+
+        assert_eq!(edges.len(), 2);
+        assert_eq!(
+            (edges[0].kind, edges[0].lines.clone()),
+            (LinkKind::Embed, vec![3])
+        );
+        assert_eq!(
+            *edges[1],
+            Edge {
+                kind: LinkKind::Link,
+                count: 3,
+                lines: vec![1, 2, 3],
+                aliases: vec!["the intro".to_string()],
+                anchors: vec!["Intro".to_string(), "^def".to_string()],
+                properties: vec!["up".to_string()],
+                contexts: vec![
+                    "up:: [[B]]".to_string(),
+                    "See [[B#Intro|the intro]]".to_string(),
+                    "![[B]] and [[B#^def]]".to_string(),
+                ],
+            }
+        );
+        assert!(edges[1].strength() > edges[0].strength());
+
+        let edge_filter = EdgeFilter {
+            embeds: false,
+            min_count: 2,
+            ..Default::default()
+        };
+        assert!(!edge_filter.matches(edges[0]) && edge_filter.matches(edges[1]));
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn unlinked_mentions() {
+        let vault_dir = create_testing_vault(
+            "unlinked_mentions",
+            &[
+                ("Plan.md", b"Mentioned by [[Log]]\n"),
+                ("sub/Plan.md", b"---\naliases: [roadmap]\n---\n"),
+                ("sub/Log.md", b"The plan changed. See the roadmap.\n"),
+            ],
+        );
+        let mut config = ScanConfig::default();
+        let (pages, report) = extract_pages(&vault_dir, &config).unwrap();
+        assert!(pages.iter().all(|page| page
+            .links()
+            .iter()
+            .all(|link| link.kind != LinkKind::Mention)));
+
+        config.include_unlinked_mentions = true;
+        let (pages, _) = update_pages(&vault_dir, &config, pages, &report, &[]).unwrap();
+        let log = pages
+            .iter()
+            .find(|page| page.path == Path::new("sub/Log.md"))
+            .unwrap();
+        let mentions: Vec<(&Path, Option<&str>)> = log
+            .links()
+            .iter()
+            .map(|link| (link.path.as_path(), link.context.as_deref()))
+            .collect();
+
+        // "plan" is shared by two notes, so only the alias counts as a mention
+        assert_eq!(
+            mentions,
+            vec![(Path::new("sub/Plan.md"), Some("See the roadmap."))]
+        );
+
+        // The text of notes is kept for mentions, even if it is not indexed
+        config.index_content = false;
+        let (pages, _) = extract_pages(&vault_dir, &config).unwrap();
+        assert!(pages.iter().any(|page| page
+            .links()
+            .iter()
+            .any(|link| link.kind == LinkKind::Mention)));
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn logseq_vault() {
+        let vault_dir = create_testing_vault(
+            "logseq_vault",
+            &[
+                ("logseq/config.edn", b"{}"),
+                ("logseq/bak/pages/Old.md", b"- [[Plan]]"),
+                (
+                    "pages/projects___plan.md",
+                    b"title:: Projects/Plan\ntags:: work\n\n- Goals\n  id:: 64a8c1f2-1b2c-4d5e-8f90-123456789abc\n",
+                ),
+                (
+                    "journals/2023_07_01.md",
+                    b"- Worked on [[projects/plan]]\n- ((64a8c1f2-1b2c-4d5e-8f90-123456789abc))\n",
+                ),
+            ],
+        );
+        let config = ScanConfig {
+            format: VaultFormat::detect(&vault_dir),
+            ..Default::default()
+        };
+
+        let (pages, report) = extract_pages(&vault_dir, &config).unwrap();
+        let page = |path: &str| {
+            pages
+                .iter()
+                .find(|page| page.path == Path::new(path))
+                .unwrap()
+        };
+        let journal = page("journals/2023_07_01.md");
+
+        assert_eq!(config.format, VaultFormat::Logseq);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(journal.title, "Jul 1st, 2023");
+        assert_eq!(page("pages/projects___plan.md").tags, vec!["work"]);
+        assert_eq!(
+            journal
+                .links()
+                .iter()
+                .map(|link| (link.path.as_path(), link.block.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new("pages/projects___plan.md"), None),
+                (
+                    Path::new("pages/projects___plan.md"),
+                    Some("64a8c1f2-1b2c-4d5e-8f90-123456789abc")
+                )
+            ]
+        );
+        assert!(report.diagnostics.is_empty());
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn notion_export() {
+        let vault_dir = create_testing_vault(
+            "notion_export",
+            &[
+                (
+                    "Projects 0123456789abcdef0123456789abcdef.md",
+                    b"# Projects\n\n[Plan](Projects%200123456789abcdef0123456789abcdef/Plan%20fedcba9876543210fedcba9876543210.md)\n",
+                ),
+                (
+                    "Projects 0123456789abcdef0123456789abcdef/Plan fedcba9876543210fedcba9876543210.md",
+                    b"# Plan\n\nBack to [Projects](../Projects%200123456789abcdef0123456789abcdef.md)\n",
+                ),
+            ],
+        );
+        let config = ScanConfig {
+            format: VaultFormat::detect(&vault_dir),
+            ..Default::default()
+        };
+
+        let (graph, report) = vault_to_graph(&vault_dir, &config).unwrap();
+        let mut titles: Vec<&str> = graph
+            .node_weights()
+            .map(|page| page.title.as_str())
+            .collect();
+        titles.sort();
+
+        assert_eq!(config.format, VaultFormat::Notion);
+        assert_eq!(titles, vec!["Plan", "Projects"]);
+        assert_eq!(graph.edge_count(), 2);
+        assert!(report.diagnostics.is_empty());
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
+
+    #[test]
+    fn scan_exclusions() {
+        let vault_dir = create_testing_vault(
+            "scan_exclusions",
+            &[
+                ("Note.md", b""),
+                ("Draft.draft.md", b""),
+                (
+                    ".obsidian/app.json",
+                    br#"{"userIgnoreFilters": ["Archive/", "/^Daily/"]}"#,
+                ),
+                (".obsidian/templates.json", br#"{"folder": "Templates"}"#),
+                (".obsidian/Settings.md", b""),
+                (".trash/Deleted.md", b""),
+                (".hidden/Hidden.md", b""),
+                ("node_modules/package/README.md", b""),
+                ("Archive/Old.md", b""),
+                ("Daily/2023-07-01.md", b""),
+                ("Templates/Template.md", b""),
+                ("folder/Nested.md", b""),
+            ],
+        );
+        // A loop is reported, a second link to a folder is skipped silently
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&vault_dir, vault_dir.join("folder/loop")).unwrap();
+            std::os::unix::fs::symlink(vault_dir.join("folder"), vault_dir.join("shortcut"))
+                .unwrap();
+        }
+
+        let config = ScanConfig {
+            exclude: vec!["*.draft.md".to_string(), "node_modules/".to_string()],
+            ..Default::default()
+        };
+        let mut diagnostics = Vec::new();
+        let mut titles: Vec<String> = search_markdown_files(&vault_dir, &config, &mut diagnostics)
+            .into_iter()
+            .map(|(_, title)| title)
+            .collect();
+        titles.sort();
+
+        assert_eq!(titles, vec!["Nested", "Note"]);
+        #[cfg(unix)]
+        assert!(matches!(&diagnostics[..], [Diagnostic::SkippedFile { .. }]));
+
+        let scanner = VaultScanner::new(&vault_dir, &config, &mut Vec::new());
+        for excluded in [
+            ".git/index",
+            ".DS_Store",
+            "node_modules/package/README.md",
+            "Daily/x.md",
+        ] {
+            assert!(scanner.excludes(Path::new(excluded)), "{}", excluded);
+        }
+        assert!(!scanner.excludes(Path::new("folder/Nested.md")));
+        let files = scanner.search_paths(
+            &[
+                PathBuf::from("folder"),
+                PathBuf::from("folder/Nested.md"),
+                PathBuf::from("Archive"),
+            ],
+            &mut Vec::new(),
+        );
+        assert_eq!(files.markdown.len(), 1);
+
+        fs::remove_dir_all(vault_dir).unwrap();
+    }
 }
@@ -0,0 +1,201 @@
+//! This module caches parsed pages on disk, so that unchanged files are not parsed again
+//!
+//! The cache is stored in the user's cache directory (`$XDG_CACHE_HOME` or `~/.cache`), rather
+//! than in the vault itself. A cached page is reused if the modification time and size of its
+//! file are unchanged, or if the contents of the file still have the same hash. The text of
+//! notes is only cached if it is [kept](super::scanner::ScanConfig::keeps_content).
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    vault_parser::{format::VaultFormat, scanner::ScanConfig, Diagnostic},
+    Page,
+};
+
+/// Name of the folder holding the cache files of all vaults
+const CACHE_FOLDER: &str = "obsidian-graph-analyser";
+
+/// Version of the cache format, to be increased whenever parsing changes
+const CACHE_VERSION: u32 = 11;
+
+/// Describes the state of a file when it was parsed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileState {
+    /// Modification time of the file (if supported by the platform)
+    pub modified: Option<SystemTime>,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+impl FileState {
+    pub fn new(metadata: &fs::Metadata) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+        }
+    }
+}
+
+/// This struct stores the parsed page of a file, along with the state of the file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// State of the file when it was parsed
+    pub state: FileState,
+    /// Hash of the contents of the file
+    pub hash: u64,
+    /// The parsed page, with unresolved links
+    pub page: Page,
+    /// Problems encountered when parsing the file
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile<E> {
+    version: u32,
+    format: VaultFormat,
+    keeps_content: bool,
+    entries: Vec<E>,
+}
+
+/// This struct holds the cached pages of a vault by their vault-relative path
+#[derive(Debug, Default)]
+pub struct ParseCache {
+    /// Location of the cache file (none if caching is disabled)
+    path: Option<PathBuf>,
+    /// Format the cached pages were parsed with
+    format: VaultFormat,
+    /// Whether the cached pages hold the text of their notes
+    keeps_content: bool,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl ParseCache {
+    /// Loads the cache of a vault.
+    ///
+    /// Missing, outdated and invalid cache files result in an empty cache, as do
+    /// cache files of the vault parsed in another format, or with(out) the text of notes.
+    pub fn load(vault_dir: &Path, config: &ScanConfig) -> Self {
+        let (format, keeps_content) = (config.format, config.keeps_content());
+        let path = cache_path(vault_dir);
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|contents| serde_json::from_slice::<CacheFile<CacheEntry>>(&contents).ok())
+            .filter(|cache| {
+                cache.version == CACHE_VERSION
+                    && cache.format == format
+                    && cache.keeps_content == keeps_content
+            })
+            .map(|cache| {
+                cache
+                    .entries
+                    .into_iter()
+                    .map(|entry| (entry.page.path.clone(), entry))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            path,
+            format,
+            keeps_content,
+            entries,
+        }
+    }
+
+    /// Returns the cached page of a file, if the file was not modified since it was parsed
+    pub fn unchanged(&self, relative_path: &Path, state: &FileState) -> Option<&CacheEntry> {
+        self.entries
+            .get(relative_path)
+            .filter(|entry| entry.state.modified.is_some() && entry.state == *state)
+    }
+
+    /// Returns the cached page of a file, if the contents of the file still have the same hash
+    pub fn same_contents(&self, relative_path: &Path, hash: u64) -> Option<&CacheEntry> {
+        self.entries
+            .get(relative_path)
+            .filter(|entry| entry.hash == hash)
+    }
+
+    /// Removes all cached pages
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+
+    /// Adds the page of a file to the cache
+    pub fn insert(&mut self, entry: CacheEntry) {
+        self.entries.insert(entry.page.path.clone(), entry);
+    }
+
+    /// Writes the cache to disk.
+    ///
+    /// The cache only speeds up loading, so failing to write it is not an error.
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let mut entries: Vec<&CacheEntry> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.page.path.cmp(&b.page.path));
+        let Ok(contents) = serde_json::to_vec(&CacheFile {
+            version: CACHE_VERSION,
+            format: self.format,
+            keeps_content: self.keeps_content,
+            entries,
+        }) else {
+            return;
+        };
+
+        // Write to a temporary file first, so that an interrupted write does not leave a partial cache
+        let temporary_path = path.with_extension("json.tmp");
+        if path
+            .parent()
+            .is_some_and(|parent| fs::create_dir_all(parent).is_ok())
+            && fs::write(&temporary_path, contents).is_ok()
+        {
+            fs::rename(&temporary_path, path).ok();
+        }
+    }
+}
+
+/// Hashes the contents of a file with 64-bit FNV-1a, which is stable across builds
+pub fn hash_contents(contents: &[u8]) -> u64 {
+    contents.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Returns the location of the cache file of a vault, in the user's cache directory
+pub fn cache_path(vault_dir: &Path) -> Option<PathBuf> {
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    // Vaults are told apart by the hash of their absolute path
+    let vault_dir = fs::canonicalize(vault_dir).ok()?;
+    let hash = hash_contents(vault_dir.as_os_str().as_encoded_bytes());
+
+    Some(
+        cache_dir
+            .join(CACHE_FOLDER)
+            .join(format!("{:016x}.json", hash)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_hash() {
+        assert_eq!(hash_contents(b""), 0xcbf29ce484222325);
+        assert_eq!(hash_contents(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
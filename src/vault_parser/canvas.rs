@@ -0,0 +1,169 @@
+//! This module parses Obsidian Canvas files (JSON Canvas format)
+//!
+//! A canvas links to every note and attachment on its file cards, to the URLs on its link
+//! cards and to the pages linked from its text cards. The edges drawn on a canvas can be
+//! imported as relations between the pages of the cards they connect.
+
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    vault_parser::{links, markdown::mask_non_prose},
+    Link, LinkKind,
+};
+
+#[derive(Deserialize)]
+struct Canvas {
+    #[serde(default)]
+    nodes: Vec<CanvasNode>,
+    #[serde(default)]
+    edges: Vec<CanvasEdge>,
+}
+
+#[derive(Deserialize)]
+struct CanvasNode {
+    id: String,
+    #[serde(rename = "type")]
+    node_type: String,
+    file: Option<String>,
+    subpath: Option<String>,
+    url: Option<String>,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CanvasEdge {
+    from_node: String,
+    to_node: String,
+    from_end: Option<String>,
+    to_end: Option<String>,
+    label: Option<String>,
+}
+
+/// Searches the contents of a canvas file for links.
+///
+/// File cards are returned as embeds, link cards as links to their URL, and text cards are
+/// searched like Markdown files (relative to `source_dir`). If `include_edges` is set, every
+/// edge between two file or link cards is returned as a [LinkKind::Relation], whose `source`
+/// is the card the edge starts from and whose `alias` is the label of the edge.
+pub fn search_canvas_links(
+    contents: &str,
+    source_dir: &Path,
+    include_edges: bool,
+) -> Result<Vec<Link>, String> {
+    let canvas: Canvas = serde_json::from_str(contents).map_err(|error| error.to_string())?;
+    let mut links = Vec::new();
+    let mut card_targets: HashMap<&str, Link> = HashMap::new();
+
+    for node in &canvas.nodes {
+        let card_link = match (node.node_type.as_str(), &node.file, &node.url, &node.text) {
+            ("file", Some(file), _, _) => Link {
+                target: file.clone(),
+                kind: LinkKind::Embed,
+                // The subpath holds the heading or block (`#Heading`, `#^block`)
+                ..links::parse_wikilink(node.subpath.as_deref().unwrap_or(""))
+            },
+            ("link", _, Some(url), _) => Link {
+                target: url.clone(),
+                ..Default::default()
+            },
+            ("text", _, _, Some(text)) => {
+                links.extend(links::search_links(&mask_non_prose(text), source_dir));
+                continue;
+            }
+            _ => continue,
+        };
+
+        card_targets.insert(&node.id, card_link.clone());
+        links.push(card_link);
+    }
+
+    if include_edges {
+        for edge in &canvas.edges {
+            let (Some(from), Some(to)) = (
+                card_targets.get(edge.from_node.as_str()),
+                card_targets.get(edge.to_node.as_str()),
+            ) else {
+                continue;
+            };
+
+            // Edges point to their end node, unless only the start has an arrow
+            let reversed =
+                edge.from_end.as_deref() == Some("arrow") && edge.to_end.as_deref() == Some("none");
+            let (from, to) = if reversed { (to, from) } else { (from, to) };
+
+            links.push(Link {
+                kind: LinkKind::Relation,
+                source: Some(from.target.clone()),
+                alias: edge.label.clone().filter(|label| !label.trim().is_empty()),
+                ..to.clone()
+            });
+        }
+    }
+
+    Ok(links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canvas_cards_and_edges() {
+        let contents = r##"{
+            "nodes": [
+                {"id": "a", "type": "file", "file": "notes/Plan.md", "subpath": "#Goals"},
+                {"id": "b", "type": "file", "file": "images/diagram.png"},
+                {"id": "c", "type": "link", "url": "https://example.com"},
+                {"id": "d", "type": "text", "text": "See [[Roadmap]] and `[[code]]`"},
+                {"id": "e", "type": "group", "label": "Group"}
+            ],
+            "edges": [
+                {"id": "1", "fromNode": "a", "toNode": "b", "label": "illustrated by"},
+                {"id": "2", "fromNode": "c", "toNode": "a", "fromEnd": "arrow", "toEnd": "none"},
+                {"id": "3", "fromNode": "a", "toNode": "d"}
+            ]
+        }"##;
+
+        let targets = |links: &[Link]| -> Vec<String> {
+            links.iter().map(|link| link.target.clone()).collect()
+        };
+        let links = search_canvas_links(contents, Path::new(""), false).unwrap();
+        assert_eq!(
+            targets(&links),
+            vec![
+                "notes/Plan.md",
+                "images/diagram.png",
+                "https://example.com",
+                "Roadmap"
+            ]
+        );
+        assert_eq!(links[0].heading.as_deref(), Some("Goals"));
+
+        let links = search_canvas_links(contents, Path::new(""), true).unwrap();
+        let relations: Vec<(Option<&str>, &str, Option<&str>)> = links
+            .iter()
+            .filter(|link| link.kind == LinkKind::Relation)
+            .map(|link| {
+                (
+                    link.source.as_deref(),
+                    link.target.as_str(),
+                    link.alias.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            relations,
+            vec![
+                (
+                    Some("notes/Plan.md"),
+                    "images/diagram.png",
+                    Some("illustrated by")
+                ),
+                (Some("notes/Plan.md"), "https://example.com", None),
+            ]
+        );
+        assert!(search_canvas_links("{", Path::new(""), false).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! This module parses inline fields (Dataview-style properties in the body of a note)
+//!
+//! A field either takes up a whole line (`parent:: [[Project X]]`, optionally in a list item,
+//! quote or bold), or is written inside brackets (`[due:: 2023-07-01]`) or parentheses
+//! (`(status:: done)`) anywhere in a line.
+
+use regex::Regex;
+use std::{collections::BTreeMap, ops::Range, sync::LazyLock};
+
+use crate::{
+    vault_parser::{
+        frontmatter::{parse_date, parse_link},
+        markdown::LineIndex,
+    },
+    PropertyValue,
+};
+
+static LINE_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)^[ \t]*(?:>[ \t]*)*(?:[-*+][ \t]+(?:\[.\][ \t]+)?|\d+[.)][ \t]+)?(?:\*\*|__)?([^\s:\[\]()*_][^:\[\]()\n]*?)(?:\*\*|__)?::(?:[ \t]+|$)([^\n]*)$",
+    )
+    .unwrap()
+});
+static BRACKETED_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[([^\[\]:()\n]+?)::[ \t]*((?:\[\[[^\]\n]*\]\]|[^\[\]\n])*)\]").unwrap()
+});
+static PARENTHESIZED_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\(([^\[\]:()\n]+?)::[ \t]*((?:\[\[[^\]\n]*\]\]|[^()\n])*)\)").unwrap()
+});
+
+/// This struct describes an inline field found in a note
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineField {
+    /// Name of the field
+    pub key: String,
+    /// Raw text of the value
+    pub value: String,
+    /// Line of the field (starting at 1)
+    pub line: usize,
+    /// Columns taken up by the value (in characters, starting at 1)
+    pub columns: Range<usize>,
+}
+
+impl InlineField {
+    /// Returns whether a position lies within the value of the field
+    pub fn contains(&self, line: usize, column: usize) -> bool {
+        self.line == line && self.columns.contains(&column)
+    }
+}
+
+/// Searches (masked) Markdown for inline fields, in order of their position
+pub fn search_inline_fields(contents: &str) -> Vec<InlineField> {
+    let index = LineIndex::new(contents);
+    let mut fields: Vec<(usize, InlineField)> = Vec::new();
+
+    for pattern in [&*LINE_FIELD, &*BRACKETED_FIELD, &*PARENTHESIZED_FIELD] {
+        for captures in pattern.captures_iter(contents) {
+            let (key, value) = (&captures[1], captures.get(2).unwrap());
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+
+            // Only the trimmed value counts, so trailing spaces are not part of the field
+            let trimmed = value.as_str().trim_end();
+            let (line, start) = index.position(value.start());
+            let (_, end) = index.position(value.start() + trimmed.len());
+            fields.push((
+                captures.get(0).unwrap().start(),
+                InlineField {
+                    key: key.to_string(),
+                    value: trimmed.to_string(),
+                    line,
+                    columns: start..end,
+                },
+            ));
+        }
+    }
+
+    fields.sort_by_key(|(offset, _)| *offset);
+    fields.into_iter().map(|(_, field)| field).collect()
+}
+
+/// Parses the text of an inline field into a typed property value.
+///
+/// Values are read as a link, a comma separated list of links, a boolean, a number
+/// or a date, and as text otherwise.
+pub fn parse_field_value(text: &str) -> PropertyValue {
+    let text = text.trim();
+
+    if let Some(link) = parse_link(text) {
+        return link;
+    }
+    if text.contains(',') {
+        let links: Option<Vec<PropertyValue>> = text.split(',').map(parse_link).collect();
+        if let Some(links) = links {
+            return PropertyValue::List(links);
+        }
+    }
+
+    match text {
+        "true" => return PropertyValue::Boolean(true),
+        "false" => return PropertyValue::Boolean(false),
+        _ => {}
+    }
+    if let Some(number) = text.parse::<f64>().ok().filter(|number| number.is_finite()) {
+        return PropertyValue::Number(number);
+    }
+
+    parse_date(text).unwrap_or_else(|| {
+        let unquoted = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(text);
+        PropertyValue::Text(unquoted.to_string())
+    })
+}
+
+/// Adds inline fields to the properties of a note.
+///
+/// Fields whose key is already present are combined with the existing value into a list.
+pub fn merge_inline_fields(
+    properties: &mut BTreeMap<String, PropertyValue>,
+    fields: &[InlineField],
+) {
+    for field in fields {
+        let value = parse_field_value(&field.value);
+
+        match properties.remove(&field.key) {
+            None => {
+                properties.insert(field.key.clone(), value);
+            }
+            Some(PropertyValue::List(mut items)) => {
+                items.push(value);
+                properties.insert(field.key.clone(), PropertyValue::List(items));
+            }
+            Some(existing) => {
+                properties.insert(
+                    field.key.clone(),
+                    PropertyValue::List(vec![existing, value]),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_shapes() {
+        let contents = "parent:: [[Project X]]\n\
+            - **Status**:: done\n\
+            Due [due:: 2023-07-01] and (rating:: 4)\n\
+            Paths like std::fs are not fields\n";
+        let found = search_inline_fields(contents);
+        let fields: Vec<(&str, &str, usize)> = found
+            .iter()
+            .map(|field| (field.key.as_str(), field.value.as_str(), field.line))
+            .collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                ("parent", "[[Project X]]", 1),
+                ("Status", "done", 2),
+                ("due", "2023-07-01", 3),
+                ("rating", "4", 3),
+            ]
+        );
+        assert_eq!(found[0].columns, 10..23);
+    }
+
+    #[test]
+    fn field_values() {
+        let link = |inner: &str| PropertyValue::Link(inner.to_string());
+
+        assert_eq!(parse_field_value(" [[Project X]] "), link("Project X"));
+        assert_eq!(
+            parse_field_value("[[A]], [[B|b]]"),
+            PropertyValue::List(vec![link("A"), link("B|b")])
+        );
+        assert_eq!(parse_field_value("true"), PropertyValue::Boolean(true));
+        assert_eq!(parse_field_value("4.5"), PropertyValue::Number(4.5));
+        assert!(matches!(
+            parse_field_value("2023-07-01"),
+            PropertyValue::Date(_)
+        ));
+        assert_eq!(
+            parse_field_value("\"in progress\""),
+            PropertyValue::Text("in progress".to_string())
+        );
+
+        let mut properties = BTreeMap::from([("due".to_string(), parse_field_value("4"))]);
+        merge_inline_fields(&mut properties, &search_inline_fields("due:: 5\n"));
+        assert_eq!(
+            properties.get("due"),
+            Some(&PropertyValue::List(vec![
+                PropertyValue::Number(4.0),
+                PropertyValue::Number(5.0)
+            ]))
+        );
+    }
+}
@@ -0,0 +1,439 @@
+//! This module describes the layouts and link syntaxes of other Markdown note-taking tools
+//!
+//! Besides Obsidian vaults, [Logseq](https://logseq.com) graphs, [Foam](https://foambubble.github.io)
+//! workspaces and [Dendron](https://www.dendron.so) vaults are parsed into the same pages and links:
+//!
+//! - Logseq keeps its pages in `pages/` and `journals/`, encodes namespaces in file names
+//!   (`a___b.md` is the page `a/b`) and refers to blocks by their id (`((uuid))`)
+//! - Foam adds Markdown link reference definitions (`[note]: note.md`) to its notes
+//! - Dendron encodes its hierarchy in file names, so `a.b.c.md` is a child of `a.b.md`
+//! - Notion exports add a page id to every file and folder name (`Page 0123...cdef.md`) and link
+//!   pages with percent-encoded Markdown links
+
+use chrono::{Datelike, NaiveDate};
+use percent_encoding::percent_decode_str;
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::LazyLock,
+};
+
+use crate::{
+    vault_parser::{
+        links::{normalize_path, parse_link_destination},
+        markdown::LineIndex,
+    },
+    Link, LinkKind, Page, PageKind, PropertyValue,
+};
+
+static NOTION_ID_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" [0-9a-f]{32}$").unwrap());
+static BLOCK_REF_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\{\{embed\s+)?\(\(([0-9a-fA-F]{8}(?:-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12})\)\)")
+        .unwrap()
+});
+
+/// This enum describes which tool a vault was written with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum VaultFormat {
+    /// Obsidian vault
+    #[default]
+    Obsidian,
+    /// Logseq graph
+    Logseq,
+    /// Foam workspace
+    Foam,
+    /// Dendron vault
+    Dendron,
+    /// Markdown export of a Notion workspace
+    Notion,
+}
+
+impl VaultFormat {
+    /// All vault formats
+    pub const ALL: [VaultFormat; 5] = [
+        VaultFormat::Obsidian,
+        VaultFormat::Logseq,
+        VaultFormat::Foam,
+        VaultFormat::Dendron,
+        VaultFormat::Notion,
+    ];
+
+    /// Guesses the format of a vault from its configuration files, defaulting to Obsidian
+    pub fn detect(vault_dir: &Path) -> Self {
+        let uses_foam = || {
+            vault_dir.join(".foam").is_dir()
+                || fs::read_to_string(vault_dir.join(".vscode/extensions.json"))
+                    .is_ok_and(|extensions| extensions.contains("foam.foam-vscode"))
+        };
+        let is_notion_export = || {
+            fs::read_dir(vault_dir).is_ok_and(|entries| {
+                entries.flatten().any(|entry| {
+                    let path = entry.path();
+                    path.extension().is_some_and(|extension| extension == "md")
+                        && NOTION_ID_PATTERN.is_match(&path.file_stem().unwrap().to_string_lossy())
+                })
+            })
+        };
+
+        if vault_dir.join(".obsidian").is_dir() {
+            VaultFormat::Obsidian
+        } else if vault_dir.join("logseq/config.edn").is_file() {
+            VaultFormat::Logseq
+        } else if vault_dir.join("dendron.yml").is_file() {
+            VaultFormat::Dendron
+        } else if uses_foam() {
+            VaultFormat::Foam
+        } else if is_notion_export() {
+            VaultFormat::Notion
+        } else {
+            VaultFormat::Obsidian
+        }
+    }
+
+    /// Returns the name of the tool
+    pub fn name(&self) -> &'static str {
+        match self {
+            VaultFormat::Obsidian => "Obsidian",
+            VaultFormat::Logseq => "Logseq",
+            VaultFormat::Foam => "Foam",
+            VaultFormat::Dendron => "Dendron",
+            VaultFormat::Notion => "Notion",
+        }
+    }
+
+    /// Checks whether a file or folder (given by its vault-relative path) is not part of the notes.
+    ///
+    /// Logseq only keeps notes in `pages/` and `journals/` (and attachments in `assets/`),
+    /// while its `logseq/` folder holds settings and backups.
+    pub fn excludes(&self, relative_path: &str, is_dir: bool) -> bool {
+        match self {
+            VaultFormat::Logseq => match relative_path.split_once('/') {
+                Some((folder, _)) => !["pages", "journals", "assets"].contains(&folder),
+                None => !is_dir || !["pages", "journals", "assets"].contains(&relative_path),
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns the title of the note at a vault-relative path
+    pub fn page_title(&self, relative_path: &Path) -> String {
+        let stem = relative_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match self {
+            VaultFormat::Logseq => {
+                let in_journals = relative_path.parent() == Some(Path::new("journals"));
+                match NaiveDate::parse_from_str(&stem, "%Y_%m_%d") {
+                    Ok(date) if in_journals => journal_title(date),
+                    _ => percent_decode_str(&stem.replace("___", "/"))
+                        .decode_utf8_lossy()
+                        .to_string(),
+                }
+            }
+            VaultFormat::Notion => NOTION_ID_PATTERN.replace(&stem, "").to_string(),
+            _ => stem,
+        }
+    }
+
+    /// Searches (masked) Markdown for the links specific to the format, in order of appearance.
+    ///
+    /// These are Logseq block references (`((uuid))` and `{{embed ((uuid))}}`), whose targets are
+    /// filled in by [link_pages](Self::link_pages), and Foam reference links (`[text][note]`).
+    pub fn search_links(&self, contents: &str, source_dir: &Path) -> Vec<Link> {
+        let line_index = LineIndex::new(contents);
+        let links: Vec<(usize, Link)> = match self {
+            VaultFormat::Logseq => search_block_refs(contents),
+            VaultFormat::Foam => search_reference_links(contents, source_dir),
+            _ => Vec::new(),
+        };
+
+        links
+            .into_iter()
+            .map(|(offset, mut link)| {
+                (link.line, link.column) = line_index.position(offset);
+                link
+            })
+            .collect()
+    }
+
+    /// Adds the links between pages which follow from the vault as a whole, before links are resolved.
+    ///
+    /// - Logseq block references are pointed to the page containing the block (`id:: uuid`),
+    ///   `title::` properties override the titles of pages and `tags::` properties add tags
+    /// - Dendron notes are linked to their nearest existing parent in the hierarchy, and `title`
+    ///   properties override the titles of notes
+    pub fn link_pages(&self, pages: &mut [Page]) {
+        match self {
+            VaultFormat::Logseq => {
+                let mut block_pages: HashMap<String, String> = HashMap::new();
+                for page in pages.iter() {
+                    for id in text_values(page.properties.get("id")) {
+                        block_pages.insert(
+                            id.to_lowercase(),
+                            normalize_path(&page.path.with_extension("")),
+                        );
+                    }
+                }
+
+                for page in pages.iter_mut() {
+                    set_title_property(page);
+
+                    for tag in logseq_tags(page.properties.get("tags")) {
+                        if !page.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                            page.tags.push(tag)
+                        }
+                    }
+
+                    for link in page.links.iter_mut() {
+                        if let (true, Some(block)) = (link.target.is_empty(), &link.block) {
+                            if let Some(path) = block_pages.get(&block.to_lowercase()) {
+                                link.target = path.clone();
+                            }
+                        }
+                    }
+                }
+            }
+            VaultFormat::Dendron => {
+                let notes: HashSet<String> = pages
+                    .iter()
+                    .filter(|page| page.kind == PageKind::Note && !page.empty)
+                    .map(|page| dendron_name(&page.path))
+                    .collect();
+
+                for page in pages.iter_mut().filter(|page| page.kind == PageKind::Note) {
+                    set_title_property(page);
+
+                    // Links are added again when pages are reused after a change
+                    page.links.retain(|link| link.kind != LinkKind::Hierarchy);
+
+                    let mut name = dendron_name(&page.path);
+                    while let Some((parent, _)) = name.rsplit_once('.') {
+                        if notes.contains(parent) {
+                            page.links.push(Link {
+                                target: parent.to_string(),
+                                kind: LinkKind::Hierarchy,
+                                ..Default::default()
+                            });
+                            break;
+                        }
+                        name = parent.to_string();
+                    }
+                }
+            }
+            VaultFormat::Obsidian | VaultFormat::Foam | VaultFormat::Notion => {}
+        }
+    }
+}
+
+// Format the title Logseq gives the journal page of a day (`Jul 1st, 2023`)
+fn journal_title(date: NaiveDate) -> String {
+    let suffix = match (date.day() % 10, date.day() % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!(
+        "{} {}{}, {}",
+        date.format("%b"),
+        date.day(),
+        suffix,
+        date.year()
+    )
+}
+
+// Get the name of a Dendron note, which is its file name without extension
+fn dendron_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// Use the `title` property of a page as its title
+fn set_title_property(page: &mut Page) {
+    if let Some(PropertyValue::Text(title)) = page.properties.get("title") {
+        if !title.trim().is_empty() {
+            page.title = title.trim().to_string();
+        }
+    }
+}
+
+// Collect the tags of a Logseq `tags::` property (`a, [[b c]], #d`)
+fn logseq_tags(value: Option<&PropertyValue>) -> Vec<String> {
+    match value {
+        Some(PropertyValue::Text(text)) => text
+            .split(',')
+            .map(|tag| {
+                let tag = tag.trim().trim_start_matches('#');
+                tag.strip_prefix("[[")
+                    .and_then(|tag| tag.strip_suffix("]]"))
+                    .unwrap_or(tag)
+                    .trim()
+                    .to_string()
+            })
+            .filter(|tag| !tag.is_empty())
+            .collect(),
+        Some(PropertyValue::Link(inner)) => vec![inner.trim().to_string()],
+        Some(PropertyValue::List(items)) => items
+            .iter()
+            .flat_map(|item| logseq_tags(Some(item)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Collect the text values of a property, which may be a list
+fn text_values(value: Option<&PropertyValue>) -> Vec<&str> {
+    match value {
+        Some(PropertyValue::Text(text)) => vec![text.trim()],
+        Some(PropertyValue::List(items)) => items
+            .iter()
+            .flat_map(|item| text_values(Some(item)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Search for Logseq block references (`((uuid))`) and block embeds (`{{embed ((uuid))}}`),
+// along with their byte offsets
+fn search_block_refs(contents: &str) -> Vec<(usize, Link)> {
+    BLOCK_REF_PATTERN
+        .captures_iter(contents)
+        .map(|capture| {
+            let link = Link {
+                kind: if capture.get(1).is_some() {
+                    LinkKind::Embed
+                } else {
+                    LinkKind::Link
+                },
+                block: Some(capture[2].to_string()),
+                ..Default::default()
+            };
+            (capture.get(0).unwrap().start(), link)
+        })
+        .collect()
+}
+
+// Search for reference links (`[text][label]`, `[label][]` and `[label]`) whose label has a
+// link reference definition (`[label]: note.md`), along with their byte offsets
+fn search_reference_links(contents: &str, source_dir: &Path) -> Vec<(usize, Link)> {
+    let bytes = contents.as_bytes();
+    let mut links = Vec::new();
+
+    for (event, range) in Parser::new_ext(contents, Options::empty()).into_offset_iter() {
+        let (kind, link_type, destination) = match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            }) => (LinkKind::Link, link_type, dest_url),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) => (LinkKind::Embed, link_type, dest_url),
+            _ => continue,
+        };
+        if !matches!(
+            link_type,
+            LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut
+        ) {
+            continue;
+        }
+
+        // Foam defines a reference for every wikilink, which is then read as `[[label]]`
+        let in_wikilink = range.start > 0
+            && bytes[range.start - 1] == b'['
+            && bytes.get(range.end) == Some(&b']');
+        if in_wikilink {
+            continue;
+        }
+
+        if let Some(link) = parse_link_destination(&destination, source_dir) {
+            links.push((range.start, Link { kind, ..link }));
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn format_specific_links() {
+        let logseq = VaultFormat::Logseq.search_links(
+            "- See ((64a8c1f2-1b2c-4d5e-8f90-123456789abc))\n\
+             - {{embed ((64A8C1F2-1B2C-4D5E-8F90-123456789ABC))}}\n",
+            Path::new("pages"),
+        );
+        assert_eq!(
+            logseq
+                .iter()
+                .map(|link| (link.kind, link.line))
+                .collect::<Vec<_>>(),
+            vec![(LinkKind::Link, 1), (LinkKind::Embed, 2)]
+        );
+
+        let foam = VaultFormat::Foam.search_links(
+            "See [[note]], [the plan][plan] and [plan].\n\n\
+             [note]: note.md \"Note\"\n[plan]: ../projects/plan.md\n",
+            Path::new("daily"),
+        );
+        assert_eq!(
+            foam.iter()
+                .map(|link| (link.target.as_str(), link.alias.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![("projects/plan", None), ("projects/plan", None)]
+        );
+    }
+
+    #[test]
+    fn titles_and_hierarchy() {
+        assert_eq!(
+            VaultFormat::Logseq.page_title(Path::new("journals/2023_07_01.md")),
+            "Jul 1st, 2023"
+        );
+        assert_eq!(
+            VaultFormat::Logseq.page_title(Path::new("pages/projects___plan%3F.md")),
+            "projects/plan?"
+        );
+        assert_eq!(
+            VaultFormat::Notion.page_title(Path::new(
+                "Projects 0123456789abcdef0123456789abcdef/Plan fedcba9876543210fedcba9876543210.md"
+            )),
+            "Plan"
+        );
+        assert!(VaultFormat::Logseq.excludes("logseq", true));
+        assert!(!VaultFormat::Logseq.excludes("pages/a.md", false));
+
+        let page = |path: &str| Page {
+            path: PathBuf::from(path),
+            ..Default::default()
+        };
+        let mut pages = vec![page("a.md"), page("a.b.c.md"), page("d.e.md")];
+        VaultFormat::Dendron.link_pages(&mut pages);
+        VaultFormat::Dendron.link_pages(&mut pages);
+
+        let parents: Vec<Vec<&str>> = pages
+            .iter()
+            .map(|page| {
+                page.links()
+                    .iter()
+                    .map(|link| link.target.as_str())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(parents, vec![vec![], vec!["a"], vec![]]);
+    }
+}
@@ -0,0 +1,282 @@
+//! This module parses the YAML frontmatter block at the start of Markdown files
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+
+use crate::{vault_parser::links::parse_wikilink, Link, PropertyValue};
+
+/// Splits a Markdown file into its frontmatter block (if any) and the remaining body.
+///
+/// A frontmatter block starts with a `---` line at the very top of the file and ends
+/// with the next `---` (or `...`) line.
+pub fn split_frontmatter(contents: &str) -> (Option<&str>, &str) {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    let mut lines = contents.split_inclusive('\n');
+    match lines.next() {
+        Some(first_line) if first_line.trim_end() == "---" => {}
+        _ => return (None, contents),
+    }
+
+    let start = contents.find('\n').map_or(contents.len(), |i| i + 1);
+    let mut offset = start;
+    for line in lines {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return (
+                Some(&contents[start..offset]),
+                &contents[offset + line.len()..],
+            );
+        }
+        offset += line.len();
+    }
+
+    // No closing delimiter, so this is not a frontmatter block
+    (None, contents)
+}
+
+/// Parses a frontmatter block into a map of typed properties
+pub fn parse_frontmatter(yaml: &str) -> Result<BTreeMap<String, PropertyValue>, String> {
+    if yaml.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    match serde_yaml::from_str::<Value>(yaml) {
+        Ok(Value::Mapping(mapping)) => Ok(mapping
+            .into_iter()
+            .map(|(key, value)| (value_to_string(&key), value_to_property(value)))
+            .collect()),
+        Ok(Value::Null) => Ok(BTreeMap::new()),
+        Ok(_) => Err("frontmatter is not a key-value mapping".to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Collects the tags stored in the `tags` (or legacy `tag`) property.
+///
+/// Tags may be written as a comma or space separated string (`tags: a, b`), a flow
+/// list (`tags: [a, b]`) or a block list. Leading `#` characters are stripped.
+pub fn frontmatter_tags(properties: &BTreeMap<String, PropertyValue>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for (key, value) in properties {
+        if key.eq_ignore_ascii_case("tags") || key.eq_ignore_ascii_case("tag") {
+            for tag in normalize_tags(value) {
+                if !tags.contains(&tag) {
+                    tags.push(tag)
+                }
+            }
+        }
+    }
+
+    tags
+}
+
+/// Returns the links in the values of frontmatter properties, labelled with the property name.
+///
+/// The links are placed on the line of their property within the file.
+pub fn frontmatter_links(yaml: &str, properties: &BTreeMap<String, PropertyValue>) -> Vec<Link> {
+    let mut links = Vec::new();
+
+    for (key, value) in properties {
+        // Lines are counted from the opening `---` line
+        let line = yaml
+            .lines()
+            .position(|line| {
+                line.strip_prefix(key.as_str())
+                    .is_some_and(|rest| rest.trim_start().starts_with(':'))
+            })
+            .map_or(0, |i| i + 2);
+
+        for inner in link_values(value) {
+            links.push(Link {
+                property: Some(key.clone()),
+                line,
+                column: 1,
+                ..parse_wikilink(inner)
+            });
+        }
+    }
+
+    links
+}
+
+// Collect the insides of all wikilinks in a property value
+fn link_values(value: &PropertyValue) -> Vec<&str> {
+    match value {
+        PropertyValue::Link(inner) => vec![inner],
+        PropertyValue::List(items) => items.iter().flat_map(link_values).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Flatten a tag property value into individual tag names
+fn normalize_tags(value: &PropertyValue) -> Vec<String> {
+    match value {
+        PropertyValue::List(items) => items.iter().flat_map(normalize_tags).collect(),
+        PropertyValue::Text(text) => text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|tag| tag.trim().trim_start_matches('#'))
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| tag.to_string())
+            .collect(),
+        PropertyValue::Number(number) => vec![number.to_string()],
+        PropertyValue::Boolean(_)
+        | PropertyValue::Date(_)
+        | PropertyValue::DateTime(_)
+        | PropertyValue::Link(_) => Vec::new(),
+    }
+}
+
+// Convert a YAML value into a typed property value
+fn value_to_property(value: Value) -> PropertyValue {
+    match value {
+        Value::Null => PropertyValue::Text(String::new()),
+        Value::Bool(boolean) => PropertyValue::Boolean(boolean),
+        Value::Number(number) => PropertyValue::Number(number.as_f64().unwrap_or(f64::NAN)),
+        Value::String(text) => parse_date(&text)
+            .or_else(|| parse_link(&text))
+            .unwrap_or(PropertyValue::Text(text)),
+        Value::Sequence(items) => match unquoted_link(&items) {
+            Some(inner) => PropertyValue::Link(inner),
+            None => PropertyValue::List(items.into_iter().map(value_to_property).collect()),
+        },
+        Value::Mapping(_) => PropertyValue::Text(value_to_string(&value)),
+        Value::Tagged(tagged) => value_to_property(tagged.value),
+    }
+}
+
+// Read an unquoted wikilink (`[[Page]]`), which YAML parses as a list within a list
+fn unquoted_link(items: &[Value]) -> Option<String> {
+    match items {
+        [Value::Sequence(inner)] => match inner.as_slice() {
+            [Value::String(text)] => Some(text.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Render a YAML value as plain text
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(boolean) => boolean.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        _ => serde_yaml::to_string(value)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+/// Tries to interpret a string as a single wikilink (`[[Page]]`)
+pub fn parse_link(text: &str) -> Option<PropertyValue> {
+    let inner = text.trim().strip_prefix("[[")?.strip_suffix("]]")?;
+
+    if inner.is_empty() || inner.contains("[[") || inner.contains("]]") {
+        None
+    } else {
+        Some(PropertyValue::Link(inner.to_string()))
+    }
+}
+
+/// Tries to interpret a string as a date (`2023-07-01`) or datetime (`2023-07-01T14:30`)
+pub fn parse_date(text: &str) -> Option<PropertyValue> {
+    let text = text.trim();
+
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(PropertyValue::Date(date));
+    }
+
+    [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .map(PropertyValue::DateTime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags_of(contents: &str) -> Vec<String> {
+        let (yaml, _) = split_frontmatter(contents);
+        frontmatter_tags(&parse_frontmatter(yaml.unwrap()).unwrap())
+    }
+
+    #[test]
+    fn tag_shapes() {
+        let expected = vec!["math".to_string(), "linear-algebra".to_string()];
+
+        assert_eq!(tags_of("---\ntags: math, linear-algebra\n---\n"), expected);
+        assert_eq!(
+            tags_of("---\ntags: [math, \"#linear-algebra\"]\n---\n"),
+            expected
+        );
+        assert_eq!(
+            tags_of("---\naliases: [Vec]\ntags:\n  - math\n  - linear-algebra\ndate: 2023-07-01\n---\nBody"),
+            expected
+        );
+    }
+
+    #[test]
+    fn typed_properties() {
+        let (yaml, body) = split_frontmatter(
+            "---\ndate: 2023-07-01\npriority: 2\ndone: false\naliases:\n  - Vec\n---\nBody",
+        );
+        let properties = parse_frontmatter(yaml.unwrap()).unwrap();
+
+        assert_eq!(body, "Body");
+        assert_eq!(
+            properties.get("date"),
+            Some(&PropertyValue::Date(
+                NaiveDate::from_ymd_opt(2023, 7, 1).unwrap()
+            ))
+        );
+        assert_eq!(
+            properties.get("priority"),
+            Some(&PropertyValue::Number(2.0))
+        );
+        assert_eq!(properties.get("done"), Some(&PropertyValue::Boolean(false)));
+        assert_eq!(
+            properties.get("aliases"),
+            Some(&PropertyValue::List(vec![PropertyValue::Text(
+                "Vec".to_string()
+            )]))
+        );
+    }
+
+    #[test]
+    fn link_properties() {
+        let (yaml, _) = split_frontmatter(
+            "---\nup: \"[[Index#Top]]\"\nparent: [[Project X]]\nrelated: [\"[[A]]\", \"[[B]] and [[C]]\"]\n---\n",
+        );
+        let properties = parse_frontmatter(yaml.unwrap()).unwrap();
+        let link = |inner: &str| PropertyValue::Link(inner.to_string());
+
+        assert_eq!(properties.get("up"), Some(&link("Index#Top")));
+        assert_eq!(properties.get("parent"), Some(&link("Project X")));
+        assert_eq!(
+            properties.get("related"),
+            Some(&PropertyValue::List(vec![
+                link("A"),
+                PropertyValue::Text("[[B]] and [[C]]".to_string())
+            ]))
+        );
+    }
+
+    #[test]
+    fn malformed_frontmatter() {
+        let (yaml, _) = split_frontmatter("---\ntags: [math\n---\n");
+
+        assert!(parse_frontmatter(yaml.unwrap()).is_err());
+        assert_eq!(split_frontmatter("No frontmatter\n---\n").0, None);
+    }
+}
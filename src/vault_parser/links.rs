@@ -0,0 +1,252 @@
+//! This module parses links between Markdown pages
+
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use std::{
+    path::{Component, Path},
+    sync::LazyLock,
+};
+
+use crate::{vault_parser::markdown::LineIndex, Link, LinkKind};
+
+static WIKILINK_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(!?)\[\[([^\[\]]+?)\]\]").unwrap());
+static MARKDOWN_LINK_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?)\[([^\[\]]*)\]\(\s*(<[^<>\n]*>|[^()\s]+)(?:\s+(?:"[^"]*"|'[^']*'))?\s*\)"#)
+        .unwrap()
+});
+static SCHEME_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9+.\-]*:").unwrap());
+
+/// Searches a Markdown file for wikilinks, embeds and Markdown links.
+///
+/// - `[[Linked page]]`, `[[folder/Linked page#Heading|Display text]]`
+/// - `![[Embedded page]]`, `![[image.png]]`
+/// - `[Display text](Linked%20page.md)`, `![alt](../images/image.png)`
+///
+/// Markdown links are resolved against `source_dir`, the vault-relative folder of the
+/// file being searched, so their targets are vault-relative paths. External URLs are skipped.
+///
+/// The contents are expected to be masked with [mask_non_prose](super::markdown::mask_non_prose),
+/// so that links in code, comments and math are skipped. Links are returned in order of appearance.
+pub fn search_links(contents: &str, source_dir: &Path) -> Vec<Link> {
+    let line_index = LineIndex::new(contents);
+    let mut links: Vec<(usize, Link)> = search_wikilinks(contents);
+    links.extend(search_markdown_links(contents, source_dir));
+    links.sort_by_key(|(offset, _)| *offset);
+
+    links
+        .into_iter()
+        .map(|(offset, mut link)| {
+            (link.line, link.column) = line_index.position(offset);
+            link
+        })
+        .collect()
+}
+
+/// Returns a copy of (masked) Markdown in which wikilinks, embeds and Markdown links are
+/// replaced by spaces, keeping line breaks, so that only unlinked text remains
+pub fn mask_links(contents: &str) -> String {
+    let mut masked = contents.to_string();
+    for pattern in [&*WIKILINK_PATTERN, &*MARKDOWN_LINK_PATTERN] {
+        masked = pattern
+            .replace_all(&masked, |captures: &regex::Captures| {
+                captures[0]
+                    .chars()
+                    .map(|c| if c == '\n' { '\n' } else { ' ' })
+                    .collect::<String>()
+            })
+            .into_owned();
+    }
+    masked
+}
+
+// Search a Markdown file for wikilinks (`[[Linked page]]`) and embeds (`![[Embedded page]]`),
+// along with their byte offsets
+fn search_wikilinks(contents: &str) -> Vec<(usize, Link)> {
+    WIKILINK_PATTERN
+        .captures_iter(contents)
+        .map(|capture| {
+            let mut link = parse_wikilink(&capture[2]);
+            if !capture[1].is_empty() {
+                link.kind = LinkKind::Embed
+            }
+            (capture.get(0).unwrap().start(), link)
+        })
+        .collect()
+}
+
+/// Splits the inside of a wikilink into its page, heading, block reference and display text.
+///
+/// - `Page#Heading` links to a heading
+/// - `Page#^block` (or `Page^block`) links to a block
+/// - `Page|Display text` sets the display text
+///
+/// The page part is empty for links within the same page (`[[#Heading]]`).
+pub fn parse_wikilink(inner: &str) -> Link {
+    // Split off the display text (escaped as `\|` inside tables)
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target.trim_end_matches('\\'), Some(alias.trim())),
+        None => (inner, None),
+    };
+
+    let (page, heading, block) = split_anchor(target);
+
+    Link {
+        target: page.trim().to_string(),
+        heading: heading.map(|heading| heading.trim().to_string()),
+        block: block.map(|block| block.trim().to_string()),
+        alias: alias
+            .filter(|alias| !alias.is_empty())
+            .map(|alias| alias.to_string()),
+        ..Default::default()
+    }
+}
+
+// Search a Markdown file for links of the form [Display text](Linked%20page.md#Heading),
+// along with their byte offsets
+fn search_markdown_links(contents: &str, source_dir: &Path) -> Vec<(usize, Link)> {
+    MARKDOWN_LINK_PATTERN
+        .captures_iter(contents)
+        .filter_map(|capture| {
+            let destination = capture[3].trim_start_matches('<').trim_end_matches('>');
+            let link = Link {
+                kind: if capture[1].is_empty() {
+                    LinkKind::Link
+                } else {
+                    LinkKind::Embed
+                },
+                alias: Some(capture[2].trim().to_string()).filter(|alias| !alias.is_empty()),
+                ..parse_link_destination(destination, source_dir)?
+            };
+            Some((capture.get(0).unwrap().start(), link))
+        })
+        .collect()
+}
+
+/// Parses the (percent-encoded) destination of a Markdown link into a link.
+///
+/// Relative destinations are resolved against `source_dir`, so the target is a vault-relative
+/// path. Returns `None` for external links (`https://...`, `mailto:...`, `obsidian://...`).
+pub fn parse_link_destination(destination: &str, source_dir: &Path) -> Option<Link> {
+    if is_url(destination) {
+        return None;
+    }
+
+    let destination = percent_decode_str(destination).decode_utf8_lossy();
+    let (path, heading, block) = split_anchor(&destination);
+
+    // Links to headings within the same page have an empty target
+    let target = if path.is_empty() {
+        String::new()
+    } else {
+        let path = path.strip_suffix(".md").unwrap_or(path);
+        match path.strip_prefix('/') {
+            Some(vault_path) => normalize_path(Path::new(vault_path)),
+            None => normalize_path(&source_dir.join(path)),
+        }
+    };
+
+    Some(Link {
+        target,
+        heading: heading.map(|heading| heading.to_string()),
+        block: block.map(|block| block.to_string()),
+        ..Default::default()
+    })
+}
+
+/// Checks whether a link target is a URL (`https://...`, `mailto:...`, `obsidian://...`)
+/// rather than a page
+pub fn is_url(target: &str) -> bool {
+    SCHEME_PATTERN.is_match(target)
+}
+
+// Split a link target into its page, heading and block reference parts
+fn split_anchor(target: &str) -> (&str, Option<&str>, Option<&str>) {
+    match target.split_once('#') {
+        Some((page, anchor)) => match anchor.strip_prefix('^') {
+            Some(block) => (page, None, Some(block)),
+            None => (page, Some(anchor), None),
+        },
+        None => match target.split_once('^') {
+            Some((page, block)) => (page, None, Some(block)),
+            None => (target, None, None),
+        },
+    }
+}
+
+/// Normalizes a vault-relative path, removing `.` and `..` components.
+///
+/// The result uses `/` as separator, regardless of the platform.
+pub fn normalize_path(path: &Path) -> String {
+    let mut components: Vec<String> = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                components.pop();
+            }
+            _ => {}
+        }
+    }
+
+    components.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wikilink_parts() {
+        let links: Vec<Link> = search_wikilinks(
+            "[[Vector#Dot product]], [[Matrix^abc123]], [[Matrix#^def456|this block]] and [[#Intro]]",
+        )
+        .into_iter()
+        .map(|(_, link)| link)
+        .collect();
+
+        assert_eq!(links[0].target, "Vector");
+        assert_eq!(links[0].heading.as_deref(), Some("Dot product"));
+        assert_eq!(links[1].target, "Matrix");
+        assert_eq!(links[1].block.as_deref(), Some("abc123"));
+        assert_eq!(links[2].block.as_deref(), Some("def456"));
+        assert_eq!(links[2].alias.as_deref(), Some("this block"));
+        assert_eq!(links[3].target, "");
+        assert_eq!(links[3].heading.as_deref(), Some("Intro"));
+    }
+
+    #[test]
+    fn embeds_and_markdown_links() {
+        let links = search_links(
+            "![[diagram.png]] [[folder/Note]] [text](Other%20Note.md) ![img](../images/a.png) \
+            [up](<../folder/Note.md#Part>) [web](https://example.com/Note.md)",
+            Path::new("notes/sub"),
+        );
+        let targets: Vec<(&str, LinkKind)> = links
+            .iter()
+            .map(|link| (link.target.as_str(), link.kind))
+            .collect();
+
+        assert_eq!(
+            targets,
+            vec![
+                ("diagram.png", LinkKind::Embed),
+                ("folder/Note", LinkKind::Link),
+                ("notes/sub/Other Note", LinkKind::Link),
+                ("notes/images/a.png", LinkKind::Embed),
+                ("notes/folder/Note", LinkKind::Link),
+            ]
+        );
+        assert_eq!(links[4].heading.as_deref(), Some("Part"));
+    }
+
+    #[test]
+    fn link_positions() {
+        let links = search_links("# Title\nSee [[A]] and\n  ünï [b](B.md)", Path::new(""));
+
+        assert_eq!((links[0].line, links[0].column), (2, 5));
+        assert_eq!((links[1].line, links[1].column), (3, 7));
+    }
+}
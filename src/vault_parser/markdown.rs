@@ -0,0 +1,166 @@
+//! This module separates the prose of a Markdown file from code, comments and math
+//!
+//! Links and tags are only searched in prose, so that `[[example]]` inside a code block
+//! or a comment does not create an edge.
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use std::ops::Range;
+
+use crate::vault_parser::frontmatter::split_frontmatter;
+
+/// Returns a copy of a Markdown file in which everything but prose is replaced by spaces.
+///
+/// Masked are code blocks, code spans, HTML (including `<!-- comments -->`), Obsidian comments
+/// (`%% comment %%`) and math (`$...$`, `$$...$$`). The frontmatter is left as is.
+/// Every masked character is replaced by a single space and line breaks are kept,
+/// so lines and columns in the copy match those in the original.
+pub fn mask_non_prose(contents: &str) -> String {
+    let (_, body) = split_frontmatter(contents);
+    let body_start = contents.len() - body.len();
+    let mut masked = vec![false; contents.len()];
+    let mut mask = |range: Range<usize>| {
+        masked[body_start + range.start..body_start + range.end].fill(true);
+    };
+
+    let parser = Parser::new_ext(body, Options::ENABLE_MATH | Options::ENABLE_TABLES);
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock)
+            | Event::Code(_)
+            | Event::InlineHtml(_)
+            | Event::Html(_)
+            | Event::InlineMath(_)
+            | Event::DisplayMath(_) => mask(range),
+            _ => {}
+        }
+    }
+
+    // Obsidian comments are not part of CommonMark, and run to the end of the file if unclosed
+    let mut comment_start: Option<usize> = None;
+    let mut i = body_start;
+    while i + 1 < contents.len() {
+        if !masked[i] && contents.as_bytes()[i] == b'%' && contents.as_bytes()[i + 1] == b'%' {
+            match comment_start.take() {
+                Some(start) => masked[start..i + 2].fill(true),
+                None => comment_start = Some(i),
+            }
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    if let Some(start) = comment_start {
+        masked[start..].fill(true);
+    }
+
+    contents
+        .char_indices()
+        .map(|(i, c)| if masked[i] && c != '\n' { ' ' } else { c })
+        .collect()
+}
+
+/// Returns the sentence around a range of a text, with whitespace collapsed.
+///
+/// Sentences end at `.`, `!` or `?` followed by a space, or at the end of a line.
+/// List markers and quote markers at the start of the line are left out.
+pub fn surrounding_sentence(text: &str, range: Range<usize>) -> String {
+    let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[range.end..]
+        .find('\n')
+        .map_or(text.len(), |i| range.end + i);
+
+    // Byte offsets just after the sentence ends within a part of the line
+    let sentence_ends = |part: Range<usize>| {
+        text[part.clone()]
+            .char_indices()
+            .filter(move |(i, c)| {
+                matches!(c, '.' | '!' | '?') && text[part.start + i + 1..].starts_with([' ', '\t'])
+            })
+            .map(move |(i, _)| part.start + i + 1)
+    };
+    let start = sentence_ends(line_start..range.start)
+        .next_back()
+        .unwrap_or(line_start);
+    let end = sentence_ends(range.end..line_end)
+        .next()
+        .unwrap_or(line_end);
+
+    text[start..end]
+        .trim_start_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '*' | '+' | '>'))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// This struct converts byte offsets in a text into lines and columns
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Indexes the starts of all lines in a text
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { text, line_starts }
+    }
+
+    /// Returns the line and column (in characters) of a byte offset, both starting at 1
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let column = self.text[self.line_starts[line - 1]..offset]
+            .chars()
+            .count()
+            + 1;
+        (line, column)
+    }
+
+    /// Returns the byte offset of a line and column (in characters), both starting at 1
+    pub fn offset(&self, line: usize, column: usize) -> usize {
+        let line_start = self.line_starts[line.clamp(1, self.line_starts.len()) - 1];
+        self.text[line_start..]
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(self.text.len(), |(i, _)| line_start + i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_regions() {
+        let contents = "---\nup: \"[[Index]]\"\n---\n\
+            Text [[A]] `[[code]]` $[[math]]$\n\
+            ```\n[[fenced]]\n```\n\
+            %% [[comment]] %% <!-- [[html]] --> [[B]]\n\
+            $$\n[[display]]\n$$\n";
+        let masked = mask_non_prose(contents);
+
+        assert_eq!(masked.lines().count(), contents.lines().count());
+        assert!(
+            masked.contains("[[Index]]") && masked.contains("[[A]]") && masked.contains("[[B]]")
+        );
+        for hidden in ["code", "math", "fenced", "comment", "html", "display"] {
+            assert!(!masked.contains(hidden), "{} was not masked", hidden);
+        }
+    }
+
+    #[test]
+    fn link_sentences() {
+        let text = "# Notes\n> First part. Then [[A]] links   here! Last part\n- Item with [[B]]\n";
+        let index = LineIndex::new(text);
+        let sentence = |line: usize, column: usize| {
+            let offset = index.offset(line, column);
+            surrounding_sentence(text, offset..offset)
+        };
+
+        assert_eq!(index.position(index.offset(2, 20)), (2, 20));
+        assert_eq!(sentence(2, 20), "Then [[A]] links here!");
+        assert_eq!(sentence(3, 13), "Item with [[B]]");
+    }
+}
@@ -0,0 +1,182 @@
+//! This module finds unlinked mentions, which are places where the title or an alias of a note
+//! appears in the text of another note without being linked
+//!
+//! Names are matched case-insensitively and only as whole words, so `plan` mentions the note
+//! `Plan` but `planning` does not. Text in links, code, comments, math and the frontmatter is
+//! skipped. Names shared by several notes are skipped as well, as it is unclear which note
+//! they mention.
+
+use regex::{Regex, RegexBuilder};
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    vault_parser::{
+        frontmatter::split_frontmatter,
+        links::{mask_links, normalize_path},
+        markdown::{mask_non_prose, surrounding_sentence, LineIndex},
+    },
+    Link, LinkKind, Page, PageKind,
+};
+
+/// Minimum length of a title or alias (in characters), so that short names do not match everywhere
+const MIN_NAME_LENGTH: usize = 3;
+
+/// This struct searches notes for the titles and aliases of all notes of a vault
+pub struct MentionFinder {
+    /// Pattern matching any name, longer names first (`None` if there are no names)
+    pattern: Option<Regex>,
+    /// Link targets (`/` and the vault-relative path without extension) by lowercase name
+    targets: HashMap<String, String>,
+}
+
+impl MentionFinder {
+    /// Collects the titles and aliases of all (non-empty) notes
+    pub fn new(pages: &[Page]) -> Self {
+        let mut targets: HashMap<String, Option<String>> = HashMap::new();
+        for page in pages
+            .iter()
+            .filter(|page| !page.empty && page.kind == PageKind::Note)
+        {
+            // A leading `/` makes the resolver match the path of the note exactly
+            let target = format!("/{}", normalize_path(&page.path.with_extension("")));
+            let mut names: Vec<String> = std::iter::once(page.title.clone())
+                .chain(page.aliases())
+                .map(|name| name.trim().to_lowercase())
+                .filter(|name| name.chars().count() >= MIN_NAME_LENGTH)
+                .collect();
+            names.sort();
+            names.dedup();
+
+            for name in names {
+                targets
+                    .entry(name)
+                    .and_modify(|existing| {
+                        if existing.as_ref() != Some(&target) {
+                            *existing = None
+                        }
+                    })
+                    .or_insert_with(|| Some(target.clone()));
+            }
+        }
+        let targets: HashMap<String, String> = targets
+            .into_iter()
+            .filter_map(|(name, target)| Some((name, target?)))
+            .collect();
+
+        let mut names: Vec<&String> = targets.keys().collect();
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        let pattern = (!names.is_empty())
+            .then(|| {
+                let alternatives: Vec<String> =
+                    names.iter().map(|name| regex::escape(name)).collect();
+                RegexBuilder::new(&alternatives.join("|"))
+                    .case_insensitive(true)
+                    .size_limit(1 << 26)
+                    .build()
+                    .ok()
+            })
+            .flatten();
+
+        Self { pattern, targets }
+    }
+
+    /// Searches the contents of the note at `source` for mentions of other notes.
+    ///
+    /// Mentions are returned as links of kind [LinkKind::Mention] in order of appearance,
+    /// each with the sentence it appears in as context.
+    pub fn search(&self, contents: &str, source: &Path) -> Vec<Link> {
+        let Some(pattern) = &self.pattern else {
+            return Vec::new();
+        };
+        let source = format!("/{}", normalize_path(&source.with_extension("")));
+
+        let prose = mask_non_prose(contents);
+        let (_, body) = split_frontmatter(&prose);
+        let body_start = prose.len() - body.len();
+        let text = mask_links(&prose);
+        let line_index = LineIndex::new(&text);
+
+        let mut mentions = Vec::new();
+        for found in pattern.find_iter(&text[body_start..]) {
+            let (start, end) = (body_start + found.start(), body_start + found.end());
+            let is_word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            if is_word_char(text[..start].chars().next_back())
+                || is_word_char(text[end..].chars().next())
+            {
+                continue;
+            }
+
+            let Some(target) = self.targets.get(&found.as_str().to_lowercase()) else {
+                continue;
+            };
+            if *target == source {
+                continue;
+            }
+
+            let (line, column) = line_index.position(start);
+            mentions.push(Link {
+                target: target.clone(),
+                kind: LinkKind::Mention,
+                line,
+                column,
+                context: Some(surrounding_sentence(&prose, start..end)),
+                ..Default::default()
+            });
+        }
+        mentions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn unlinked_mentions() {
+        let page = |path: &str, title: &str| Page {
+            title: title.to_string(),
+            path: PathBuf::from(path),
+            ..Default::default()
+        };
+        let mut travel = page("Travel plans.md", "Travel plans");
+        travel.properties.insert(
+            "aliases".to_string(),
+            crate::PropertyValue::List(vec![crate::PropertyValue::Text("trips".to_string())]),
+        );
+        let finder = MentionFinder::new(&[
+            travel,
+            page("Budget.md", "Budget"),
+            page("a/Ideas.md", "Ideas"),
+            page("b/Ideas.md", "Ideas"),
+            page("Notes.md", "Notes"),
+        ]);
+
+        let mentions = finder.search(
+            "---\nup: Budget\n---\n\
+            Our travel plans are ready. The BUDGET is not! Budgeting [[Budget]] `budget`\n\
+            - Some ideas for trips in Notes\n",
+            Path::new("Notes.md"),
+        );
+        let found: Vec<(&str, usize, usize, &str)> = mentions
+            .iter()
+            .map(|link| {
+                (
+                    link.target.as_str(),
+                    link.line,
+                    link.column,
+                    link.context.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                ("/Travel plans", 4, 5, "Our travel plans are ready."),
+                ("/Budget", 4, 33, "The BUDGET is not!"),
+                ("/Travel plans", 5, 18, "Some ideas for trips in Notes"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! This module resolves link targets to pages, following the rules of ObsidianMD
+//!
+//! - Targets are matched case-insensitively, with or without `.md` extension
+//!   (attachments and canvases are matched with their extension)
+//! - `[[Page]]` matches any page named `Page`, `[[folder/Page]]` matches by (partial) path
+//! - If several pages match, a page in the folder of the linking page is preferred, then an
+//!   attachment in the attachment folder of the vault settings. Otherwise the page with the
+//!   shortest path is chosen and the link is reported as ambiguous
+//! - Targets which match no page name are matched against page aliases
+//! - Targets which match no page path are matched against page titles, which differ from
+//!   file names in some vault formats (such as Logseq's `a___b.md` for the page `a/b`)
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    vault_parser::{links::normalize_path, scanner::is_attachment},
+    Page, PageKind,
+};
+
+/// Describes the outcome of resolving a link target
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    /// The target matches exactly one page
+    Resolved(PathBuf),
+    /// The target matches several pages, of which the first one was chosen
+    Ambiguous(PathBuf, Vec<PathBuf>),
+    /// The target matches no page
+    Unresolved,
+}
+
+/// This struct looks up pages by name, path, alias or title
+pub struct LinkResolver {
+    /// Page paths by lowercase vault-relative path without extension
+    paths: HashMap<String, PathBuf>,
+    /// Page paths by lowercase file name without extension
+    names: HashMap<String, Vec<PathBuf>>,
+    /// Page paths by lowercase alias
+    aliases: HashMap<String, Vec<PathBuf>>,
+    /// Note paths by lowercase title
+    titles: HashMap<String, Vec<PathBuf>>,
+    /// Attachment folder, as set in the vault settings
+    attachment_folder: Option<String>,
+}
+
+impl LinkResolver {
+    /// Indexes the paths, aliases and titles of all (non-empty) pages.
+    ///
+    /// `attachment_folder` is the folder set in the vault settings (`attachmentFolderPath`).
+    pub fn new(pages: &[Page], attachment_folder: Option<&str>) -> Self {
+        let mut paths = HashMap::new();
+        let mut names: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut aliases: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut titles: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+        for page in pages.iter().filter(|page| !page.empty) {
+            let key = match page.kind {
+                PageKind::Note => normalize_path(&page.path.with_extension("")),
+                PageKind::Attachment { .. } | PageKind::Canvas => normalize_path(&page.path),
+                PageKind::Url => continue,
+            }
+            .to_lowercase();
+            let name = key.rsplit('/').next().unwrap_or(&key).to_string();
+
+            paths.insert(key, page.path.clone());
+            names.entry(name).or_default().push(page.path.clone());
+            if page.kind == PageKind::Note && !page.title.is_empty() {
+                titles
+                    .entry(page.title.to_lowercase())
+                    .or_default()
+                    .push(page.path.clone());
+            }
+            for alias in page.aliases() {
+                aliases
+                    .entry(alias.to_lowercase())
+                    .or_default()
+                    .push(page.path.clone());
+            }
+        }
+
+        Self {
+            paths,
+            names,
+            aliases,
+            titles,
+            attachment_folder: attachment_folder.map(str::to_string),
+        }
+    }
+
+    /// Resolves a link target written in the page at `source`.
+    ///
+    /// An empty target refers to the source page itself (`[[#Heading]]`).
+    pub fn resolve(&self, target: &str, source: &Path) -> Resolution {
+        let target = target.trim();
+        let target = target.strip_suffix(".md").unwrap_or(target).to_lowercase();
+
+        if target.is_empty() {
+            return Resolution::Resolved(source.to_path_buf());
+        }
+
+        if target.contains('/') {
+            let target = target.trim_start_matches('/');
+            if let Some(path) = self.paths.get(target) {
+                return Resolution::Resolved(path.clone());
+            }
+
+            // Partial paths match the end of a page path
+            let suffix = format!("/{}", target);
+            let candidates: Vec<PathBuf> = self
+                .paths
+                .iter()
+                .filter(|(key, _)| key.ends_with(&suffix))
+                .map(|(_, path)| path.clone())
+                .collect();
+            if !candidates.is_empty() {
+                return self.choose_candidate(candidates, source);
+            }
+        }
+
+        match self
+            .names
+            .get(&target)
+            .or_else(|| self.aliases.get(&target))
+            .or_else(|| self.titles.get(&target))
+        {
+            Some(candidates) => self.choose_candidate(candidates.clone(), source),
+            None => Resolution::Unresolved,
+        }
+    }
+
+    // Choose a page out of several matching candidates
+    fn choose_candidate(&self, mut candidates: Vec<PathBuf>, source: &Path) -> Resolution {
+        candidates.sort_by_key(|path| (path.components().count(), path.clone()));
+        candidates.dedup();
+
+        match candidates.len() {
+            0 => Resolution::Unresolved,
+            1 => Resolution::Resolved(candidates.remove(0)),
+            _ => {
+                // Prefer a page in the same folder as the linking page, then an attachment
+                // in the attachment folder
+                let same_folder: Vec<&PathBuf> = candidates
+                    .iter()
+                    .filter(|path| path.parent() == source.parent())
+                    .collect();
+                let attachment_folder = self.attachment_folder(source);
+                let in_attachment_folder: Vec<&PathBuf> = candidates
+                    .iter()
+                    .filter(|path| {
+                        is_attachment(path)
+                            && attachment_folder
+                                .as_deref()
+                                .is_some_and(|folder| path.parent() == Some(folder))
+                    })
+                    .collect();
+
+                match (same_folder.as_slice(), in_attachment_folder.as_slice()) {
+                    ([path], _) | (_, [path]) => Resolution::Resolved(PathBuf::clone(path)),
+                    _ => Resolution::Ambiguous(candidates[0].clone(), candidates),
+                }
+            }
+        }
+    }
+
+    // Folder the attachments of a note are stored in, relative to the vault
+    fn attachment_folder(&self, source: &Path) -> Option<PathBuf> {
+        let folder = self.attachment_folder.as_deref()?.trim();
+        let note_folder = source.parent().unwrap_or(Path::new(""));
+
+        Some(match folder.strip_prefix('.') {
+            Some(subfolder) if subfolder.is_empty() || subfolder.starts_with('/') => {
+                note_folder.join(subfolder.trim_matches('/'))
+            }
+            _ => PathBuf::from(folder.trim_matches('/')),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(path: &str) -> Page {
+        Page {
+            path: PathBuf::from(path),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn obsidian_resolution_rules() {
+        let resolver = LinkResolver::new(
+            &[
+                page("Vector.md"),
+                page("a/README.md"),
+                page("b/README.md"),
+                page("b/docs/README.md"),
+            ],
+            None,
+        );
+        let source = Path::new("b/Notes.md");
+
+        assert_eq!(
+            resolver.resolve("vector", source),
+            Resolution::Resolved(PathBuf::from("Vector.md"))
+        );
+        assert_eq!(
+            resolver.resolve("README", source),
+            Resolution::Resolved(PathBuf::from("b/README.md"))
+        );
+        assert_eq!(
+            resolver.resolve("docs/readme", source),
+            Resolution::Resolved(PathBuf::from("b/docs/README.md"))
+        );
+        assert!(matches!(
+            resolver.resolve("README", Path::new("Index.md")),
+            Resolution::Ambiguous(_, candidates) if candidates.len() == 3
+        ));
+        assert_eq!(resolver.resolve("Scalar", source), Resolution::Unresolved);
+    }
+
+    #[test]
+    fn attachment_folder() {
+        let attachment = |path: &str| Page {
+            kind: PageKind::Attachment {
+                file_type: "png".to_string(),
+                size: 0,
+            },
+            ..page(path)
+        };
+        let pages = [
+            attachment("a/diagram.png"),
+            attachment("assets/diagram.png"),
+            attachment("b/files/diagram.png"),
+        ];
+        let resolve = |attachment_folder: &str, source: &str| {
+            LinkResolver::new(&pages, Some(attachment_folder))
+                .resolve("diagram.png", Path::new(source))
+        };
+        let resolved = |path: &str| Resolution::Resolved(PathBuf::from(path));
+
+        assert_eq!(
+            resolve("assets", "b/Notes.md"),
+            resolved("assets/diagram.png")
+        );
+        assert_eq!(
+            resolve("./files", "b/Notes.md"),
+            resolved("b/files/diagram.png")
+        );
+        // Attachments in the folder of the linking note are still preferred
+        assert_eq!(resolve("assets", "a/Notes.md"), resolved("a/diagram.png"));
+        assert!(matches!(
+            resolve("/", "b/Notes.md"),
+            Resolution::Ambiguous(_, candidates) if candidates.len() == 3
+        ));
+    }
+}
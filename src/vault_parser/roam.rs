@@ -0,0 +1,291 @@
+//! This module imports Roam Research JSON exports
+//!
+//! A Roam export is a single JSON file holding the tree of blocks of every page. Each page is
+//! turned into a note listing its blocks, so that links, tags and attributes (`key:: value`) are
+//! found as in Markdown files. Roam tags (`#tag`, `#[[tag]]`) link to pages as well, and block
+//! references (`((uid))`) link to the page containing the block.
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    vault_parser::{
+        fields, format::VaultFormat, markdown, markdown::LineIndex, parse_markdown, resolve_links,
+        ParseReport, VaultError,
+    },
+    Link, LinkKind, Page,
+};
+
+static TAG_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[\s(])#(\[\[[^\[\]\n]+\]\]|[\w\-/]*[\w\-])").unwrap());
+static BLOCK_REF_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\{\{(?:\[\[)?embed(?:\]\])?:\s*)?\(\(([\w\-]+)\)\)").unwrap());
+
+#[derive(Deserialize)]
+struct RoamPage {
+    title: String,
+    #[serde(default)]
+    children: Vec<RoamBlock>,
+    #[serde(rename = "create-time")]
+    create_time: Option<u64>,
+    #[serde(rename = "edit-time")]
+    edit_time: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RoamBlock {
+    #[serde(default)]
+    string: String,
+    uid: Option<String>,
+    heading: Option<usize>,
+    #[serde(default)]
+    children: Vec<RoamBlock>,
+}
+
+/// Checks whether a file is a Roam Research JSON export: a `.json` file holding an array of
+/// pages, which are objects with a `title` and blocks with a `string` as `children`
+pub fn is_roam_export(path: &Path) -> bool {
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension == "json");
+    if !is_json || !path.is_file() {
+        return false;
+    }
+    let Some(Value::Array(pages)) = fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+    else {
+        return false;
+    };
+
+    !pages.is_empty()
+        && pages.iter().all(|page| {
+            page.get("title").is_some_and(Value::is_string) && are_blocks(page.get("children"))
+        })
+}
+
+// Check whether the children of a page or block (if any) are blocks with a `string`
+fn are_blocks(children: Option<&Value>) -> bool {
+    match children {
+        None => true,
+        Some(Value::Array(blocks)) => blocks.iter().all(|block| {
+            block.get("string").is_some_and(Value::is_string) && are_blocks(block.get("children"))
+        }),
+        Some(_) => false,
+    }
+}
+
+/// Imports the pages of a Roam Research JSON export.
+///
+/// Pages are given the path `<title>.md`, so namespaced pages (`a/b`) end up in folders.
+/// Links are resolved as in Obsidian vaults, with unresolved links reported in the [ParseReport].
+pub fn import_roam_export(file: &Path) -> Result<(Vec<Page>, ParseReport), VaultError> {
+    let contents =
+        fs::read(file).map_err(|error| VaultError::Unreadable(file.to_path_buf(), error))?;
+    let roam_pages: Vec<RoamPage> = serde_json::from_slice(&contents)
+        .map_err(|error| VaultError::InvalidExport(file.to_path_buf(), error.to_string()))?;
+
+    let mut report = ParseReport::default();
+    let mut block_pages: HashMap<String, String> = HashMap::new();
+    let mut notes: Vec<(&RoamPage, PathBuf, String)> = Vec::new();
+    for roam_page in roam_pages
+        .iter()
+        .filter(|page| !page.title.trim().is_empty())
+    {
+        let mut body = String::new();
+        write_blocks(&roam_page.children, 0, &mut body, &mut |uid| {
+            block_pages.insert(uid.to_string(), roam_page.title.clone());
+        });
+
+        let path = PathBuf::from(format!("{}.md", roam_page.title.trim_matches('/')));
+        notes.push((roam_page, path, body));
+    }
+
+    let mut pages = Vec::with_capacity(notes.len());
+    for (roam_page, path, body) in notes {
+        let mut page = parse_markdown(
+            &body,
+            &path,
+            &roam_page.title,
+            VaultFormat::Obsidian,
+            &mut report.diagnostics,
+        );
+
+        let prose = markdown::mask_non_prose(&body);
+        let line_index = LineIndex::new(&prose);
+        let fields = fields::search_inline_fields(&prose);
+        for (offset, link) in search_roam_links(&prose, &mut page.tags, &block_pages) {
+            let (line, column) = line_index.position(offset);
+            page.links.push(Link {
+                line,
+                column,
+                context: Some(markdown::surrounding_sentence(&prose, offset..offset)),
+                property: fields
+                    .iter()
+                    .find(|field| field.contains(line, column))
+                    .map(|field| field.key.clone()),
+                ..link
+            });
+        }
+        page.links.sort_by_key(|link| (link.line, link.column));
+
+        let timestamp = |millis: u64| SystemTime::UNIX_EPOCH + Duration::from_millis(millis);
+        page.stats.created = roam_page.create_time.map(timestamp);
+        page.stats.modified = roam_page.edit_time.map(timestamp);
+
+        pages.push(page);
+        report.parsed_files += 1;
+    }
+
+    resolve_links(&mut pages, None, &mut report.diagnostics);
+    Ok((pages, report))
+}
+
+// Write a tree of blocks as a nested Markdown list, passing the uid of every block to `on_uid`
+fn write_blocks(
+    blocks: &[RoamBlock],
+    depth: usize,
+    body: &mut String,
+    on_uid: &mut impl FnMut(&str),
+) {
+    for block in blocks {
+        if let Some(uid) = &block.uid {
+            on_uid(uid);
+        }
+
+        let indent = "  ".repeat(depth);
+        let heading = match block.heading {
+            Some(level @ 1..=6) => format!("{} ", "#".repeat(level)),
+            _ => String::new(),
+        };
+        let text = block.string.replace('\n', &format!("\n{}  ", indent));
+        body.push_str(&format!("{}- {}{}\n", indent, heading, text));
+
+        write_blocks(&block.children, depth + 1, body, on_uid);
+    }
+}
+
+// Search (masked) block text for tags, which link to pages in Roam, and for block references,
+// along with their byte offsets. Tags are added to `tags`.
+fn search_roam_links(
+    prose: &str,
+    tags: &mut Vec<String>,
+    block_pages: &HashMap<String, String>,
+) -> Vec<(usize, Link)> {
+    let mut links = Vec::new();
+
+    for capture in TAG_PATTERN.captures_iter(prose) {
+        let tag = capture.get(1).unwrap();
+        let (name, is_link) = match tag.as_str().strip_prefix("[[") {
+            // The page of `#[[tag]]` is already linked as a wikilink
+            Some(name) => (name.trim_end_matches("]]"), false),
+            None => (tag.as_str(), true),
+        };
+
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(name)) {
+            tags.push(name.to_string())
+        }
+        if is_link {
+            let link = Link {
+                target: name.to_string(),
+                ..Default::default()
+            };
+            links.push((tag.start() - 1, link));
+        }
+    }
+
+    for capture in BLOCK_REF_PATTERN.captures_iter(prose) {
+        if let Some(title) = block_pages.get(&capture[2]) {
+            let link = Link {
+                target: title.clone(),
+                kind: if capture.get(1).is_some() {
+                    LinkKind::Embed
+                } else {
+                    LinkKind::Link
+                },
+                block: Some(capture[2].to_string()),
+                ..Default::default()
+            };
+            links.push((capture.get(0).unwrap().start(), link));
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roam_export() {
+        let file = std::env::temp_dir().join(format!("roam_export_{}.json", std::process::id()));
+        fs::write(
+            &file,
+            r#"[
+                {"title": "Project", "create-time": 1688169600000, "children": [
+                    {"string": "Goal", "uid": "abcDEF123", "heading": 2, "children": [
+                        {"string": "status:: [[Active]] #work #[[deep work]]", "uid": "ghiJKL456"}
+                    ]}
+                ]},
+                {"title": "July 1st, 2023", "children": [
+                    {"string": "Worked on ((abcDEF123)) and [[Missing]]", "uid": "mnoPQR789"}
+                ]}
+            ]"#,
+        )
+        .unwrap();
+
+        let (pages, report) = import_roam_export(&file).unwrap();
+        let page = |title: &str| pages.iter().find(|page| page.title == title).unwrap();
+        let targets = |page: &Page| -> Vec<(String, Option<String>)> {
+            page.links()
+                .iter()
+                .map(|link| (link.target.clone(), link.property.clone()))
+                .collect()
+        };
+
+        assert_eq!(report.parsed_files, 2);
+        assert_eq!(page("Project").tags, vec!["work", "deep work"]);
+        assert_eq!(page("Project").stats.headings[0].text, "Goal");
+        assert!(page("Project").stats.created.is_some());
+        assert_eq!(
+            targets(page("Project")),
+            vec![
+                ("Active".to_string(), Some("status".to_string())),
+                ("work".to_string(), Some("status".to_string())),
+                ("deep work".to_string(), Some("status".to_string())),
+            ]
+        );
+        assert_eq!(
+            page("July 1st, 2023").links()[0].path,
+            PathBuf::from("Project.md")
+        );
+        assert_eq!(report.diagnostics.len(), 4);
+        assert!(matches!(
+            import_roam_export(Path::new("missing.json")),
+            Err(VaultError::Unreadable(..))
+        ));
+        assert!(is_roam_export(&file));
+
+        // Other JSON files are not mistaken for exports
+        for other in [
+            r#"{"name": "package"}"#,
+            r#"["a", "b"]"#,
+            "[]",
+            r#"[{"title": "A", "children": [{"text": "B"}]}]"#,
+        ] {
+            fs::write(&file, other).unwrap();
+            assert!(!is_roam_export(&file), "{}", other);
+        }
+
+        fs::remove_file(file).unwrap();
+    }
+}
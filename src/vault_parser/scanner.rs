@@ -0,0 +1,487 @@
+//! This module searches vaults for the files to parse
+//!
+//! Which files are scanned is determined by a [ScanConfig] and by the settings
+//! of the vault itself ([ObsidianSettings]).
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::vault_parser::{format::VaultFormat, links::normalize_path, Diagnostic};
+
+/// This struct configures which files and folders of a vault are scanned.
+#[derive(Debug, Clone)]
+pub struct ScanConfig {
+    /// Gitignore-style patterns of files to include (all files if empty)
+    pub include: Vec<String>,
+    /// Gitignore-style patterns of files and folders to exclude
+    pub exclude: Vec<String>,
+    /// Whether to skip hidden files and folders (starting with `.`)
+    pub skip_hidden: bool,
+    /// Whether to follow symbolic links (symbolic link loops are always skipped)
+    pub follow_symlinks: bool,
+    /// Whether to honour the excluded files and template folder set in `.obsidian`
+    pub use_obsidian_settings: bool,
+    /// Whether to reuse the pages of unchanged files from the [ParseCache](super::cache::ParseCache)
+    pub use_cache: bool,
+    /// Whether to add attachments (images, PDFs, audio, ...) as pages
+    ///
+    /// Links to attachments are resolved either way, so they never turn into empty pages.
+    pub include_attachments: bool,
+    /// Whether to add the edges drawn on canvases as relations between the pages they connect
+    pub include_canvas_edges: bool,
+    /// Whether to keep the text of notes, for full-text filters (`content:`, `line:`, `section:`)
+    pub index_content: bool,
+    /// Whether to search notes for unlinked mentions of other notes (see [mentions](super::mentions))
+    ///
+    /// Mentions are searched in the text of notes, which is kept for them even if it is not indexed.
+    pub include_unlinked_mentions: bool,
+    /// Tool the vault was written with, which determines its layout and link syntax
+    pub format: VaultFormat,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: vec![
+                "/.obsidian/".to_string(),
+                "/.trash/".to_string(),
+                "node_modules/".to_string(),
+            ],
+            skip_hidden: true,
+            follow_symlinks: true,
+            use_obsidian_settings: true,
+            use_cache: false,
+            include_attachments: false,
+            include_canvas_edges: false,
+            index_content: false,
+            include_unlinked_mentions: false,
+            format: VaultFormat::Obsidian,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// Returns whether the text of notes is kept on their pages, to be indexed or searched for
+    /// unlinked mentions
+    pub fn keeps_content(&self) -> bool {
+        self.index_content || self.include_unlinked_mentions
+    }
+}
+
+/// This struct stores the settings of a vault relevant to scanning, read from its `.obsidian` folder.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ObsidianSettings {
+    /// Paths and `/regex/` patterns set as "Excluded files"
+    #[serde(rename = "userIgnoreFilters", default)]
+    pub excluded_files: Vec<String>,
+    /// Folder in which new attachments are stored (`/` for the vault folder, `./` for the folder
+    /// of the note, `./folder` for a subfolder of it)
+    #[serde(rename = "attachmentFolderPath", default)]
+    pub attachment_folder: Option<String>,
+    /// Folder containing the templates of the core Templates plugin
+    #[serde(skip)]
+    pub template_folder: Option<String>,
+    /// Compiled `/regex/` patterns out of the excluded files
+    #[serde(skip)]
+    excluded_patterns: Vec<Regex>,
+}
+
+#[derive(Deserialize)]
+struct TemplateSettings {
+    folder: Option<String>,
+}
+
+impl ObsidianSettings {
+    /// Reads `.obsidian/app.json` and `.obsidian/templates.json`.
+    ///
+    /// Missing settings files are ignored, invalid ones are reported as diagnostics.
+    pub fn load(vault_dir: &Path, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let mut settings: ObsidianSettings =
+            read_settings_file(vault_dir, "app.json", diagnostics).unwrap_or_default();
+
+        if let Some(templates) =
+            read_settings_file::<TemplateSettings>(vault_dir, "templates.json", diagnostics)
+        {
+            settings.template_folder = templates.folder;
+        }
+
+        // Compile the regex filters once, rather than for every scanned file
+        settings.excluded_patterns = settings
+            .excluded_files
+            .iter()
+            .filter_map(|filter| filter.strip_prefix('/')?.strip_suffix('/'))
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect();
+
+        settings
+    }
+
+    // Check whether a vault-relative path is excluded by the vault settings
+    fn excludes(&self, relative_path: &str) -> bool {
+        let in_folder = |folder: &str| {
+            let folder = folder.trim_matches('/');
+            !folder.is_empty()
+                && (relative_path == folder || relative_path.starts_with(&format!("{}/", folder)))
+        };
+
+        self.template_folder.as_deref().is_some_and(in_folder)
+            || self
+                .excluded_patterns
+                .iter()
+                .any(|regex| regex.is_match(relative_path))
+            || self
+                .excluded_files
+                .iter()
+                .filter(|filter| !(filter.starts_with('/') && filter.ends_with('/')))
+                .any(|filter| in_folder(filter) || relative_path.starts_with(filter.as_str()))
+    }
+}
+
+/// File extensions of the attachments supported by Obsidian
+pub const ATTACHMENT_EXTENSIONS: &[&str] = &[
+    "avif", "bmp", "gif", "jpeg", "jpg", "png", "svg", "webp", // Images
+    "flac", "m4a", "mp3", "ogg", "wav", "3gp", // Audio
+    "mkv", "mov", "mp4", "ogv", "webm", // Video
+    "pdf",
+];
+
+/// Checks whether a file is an attachment, based on its extension
+pub fn is_attachment(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| ATTACHMENT_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+// Read and deserialize a JSON file from the `.obsidian` folder
+fn read_settings_file<T: for<'de> Deserialize<'de>>(
+    vault_dir: &Path,
+    name: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<T> {
+    let relative_path = Path::new(".obsidian").join(name);
+    let contents = fs::read_to_string(vault_dir.join(&relative_path)).ok()?;
+
+    match serde_json::from_str(&contents) {
+        Ok(settings) => Some(settings),
+        Err(error) => {
+            diagnostics.push(Diagnostic::UnreadableFile {
+                file: relative_path,
+                message: error.to_string(),
+            });
+            None
+        }
+    }
+}
+
+// Build a gitignore-style matcher from a list of patterns
+fn build_matcher(
+    vault_dir: &Path,
+    patterns: &[String],
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(vault_dir);
+    for pattern in patterns.iter().filter(|pattern| !pattern.trim().is_empty()) {
+        if let Err(error) = builder.add_line(None, pattern.trim()) {
+            diagnostics.push(Diagnostic::SkippedFile {
+                file: PathBuf::from(pattern),
+                reason: format!("invalid pattern: {}", error),
+            });
+        }
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// This struct lists the files found when scanning a vault
+#[derive(Debug, Clone, Default)]
+pub struct VaultFiles {
+    /// Absolute path and title of every Markdown file
+    pub markdown: Vec<(PathBuf, String)>,
+    /// Absolute path of every attachment
+    pub attachments: Vec<PathBuf>,
+    /// Absolute path of every canvas
+    pub canvases: Vec<PathBuf>,
+    /// Attachment folder set in the vault settings, which links to attachments prefer
+    pub attachment_folder: Option<String>,
+}
+
+impl VaultFiles {
+    /// Keeps only the files for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        self.markdown.retain(|(file, _)| keep(file));
+        self.attachments.retain(|file| keep(file));
+        self.canvases.retain(|file| keep(file));
+    }
+
+    /// Adds the files of another scan
+    pub fn extend(&mut self, other: VaultFiles) {
+        self.markdown.extend(other.markdown);
+        self.attachments.extend(other.attachments);
+        self.canvases.extend(other.canvases);
+    }
+}
+
+/// Searches a vault and all subfolders (recursively) for Markdown files.
+///
+/// Returns the absolute path and title of every file. Folders which cannot be read,
+/// files without a valid UTF-8 name and symbolic link loops are reported as diagnostics.
+/// Folders reached through several symbolic links are only scanned once.
+pub fn search_markdown_files(
+    vault_dir: &Path,
+    config: &ScanConfig,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<(PathBuf, String)> {
+    search_vault_files(vault_dir, config, diagnostics).markdown
+}
+
+/// Searches a vault and all subfolders (recursively) for Markdown files, attachments and canvases.
+///
+/// Problems are reported as with [search_markdown_files].
+pub fn search_vault_files(
+    vault_dir: &Path,
+    config: &ScanConfig,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> VaultFiles {
+    let scanner = VaultScanner::new(vault_dir, config, diagnostics);
+
+    let mut files = VaultFiles {
+        attachment_folder: scanner.settings.attachment_folder.clone(),
+        ..Default::default()
+    };
+    let mut visited = HashSet::new();
+    scanner.search_folder(
+        vault_dir,
+        &mut Vec::new(),
+        &mut visited,
+        &mut files,
+        diagnostics,
+    );
+    files
+}
+
+/// This struct decides which files and folders of a vault are scanned, using a [ScanConfig]
+/// and the settings of the vault.
+pub struct VaultScanner {
+    vault_dir: PathBuf,
+    config: ScanConfig,
+    settings: ObsidianSettings,
+    include: Gitignore,
+    exclude: Gitignore,
+}
+
+impl VaultScanner {
+    /// Reads the vault settings (if enabled) and compiles the patterns of a [ScanConfig].
+    ///
+    /// Invalid settings and patterns are reported as diagnostics.
+    pub fn new(vault_dir: &Path, config: &ScanConfig, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let settings = if config.use_obsidian_settings {
+            ObsidianSettings::load(vault_dir, diagnostics)
+        } else {
+            ObsidianSettings::default()
+        };
+
+        Self {
+            vault_dir: vault_dir.to_path_buf(),
+            config: config.clone(),
+            settings,
+            include: build_matcher(vault_dir, &config.include, diagnostics),
+            exclude: build_matcher(vault_dir, &config.exclude, diagnostics),
+        }
+    }
+
+    /// Checks whether a vault-relative path, or any folder containing it, is left out of scans.
+    ///
+    /// Paths which no longer exist are checked as files.
+    pub fn excludes(&self, relative_path: &Path) -> bool {
+        let path = self.vault_dir.join(relative_path);
+        path.ancestors()
+            .take_while(|ancestor| *ancestor != self.vault_dir)
+            .any(|ancestor| self.is_excluded(ancestor, ancestor != path || path.is_dir()))
+    }
+
+    /// Searches the given vault-relative files and folders (recursively), as a scan of the whole
+    /// vault would.
+    ///
+    /// Paths which no longer exist or are excluded are skipped, so are paths within other
+    /// given folders.
+    pub fn search_paths(
+        &self,
+        relative_paths: &[PathBuf],
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> VaultFiles {
+        let mut files = VaultFiles::default();
+        let mut visited = HashSet::new();
+
+        for relative_path in relative_paths {
+            let within_other = relative_paths
+                .iter()
+                .any(|other| other != relative_path && relative_path.starts_with(other));
+            let path = self.vault_dir.join(relative_path);
+            if within_other || !path.exists() || self.excludes(relative_path) {
+                continue;
+            }
+
+            if path.is_dir() {
+                // Links within the folder to the folders containing it are loops as well
+                let mut ancestors: Vec<PathBuf> = path
+                    .ancestors()
+                    .skip(1)
+                    .take_while(|ancestor| ancestor.starts_with(&self.vault_dir))
+                    .filter_map(|ancestor| fs::canonicalize(ancestor).ok())
+                    .collect();
+                self.search_folder(&path, &mut ancestors, &mut visited, &mut files, diagnostics);
+            } else if self.is_included(&path) {
+                self.add_file(path, &mut files, diagnostics);
+            }
+        }
+
+        files
+    }
+
+    // Scan a folder recursively. `ancestors` holds the canonical paths of the folders being
+    // scanned, `visited` those of all folders scanned so far.
+    fn search_folder(
+        &self,
+        folder_path: &Path,
+        ancestors: &mut Vec<PathBuf>,
+        visited: &mut HashSet<PathBuf>,
+        files: &mut VaultFiles,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let canonical_path = fs::canonicalize(folder_path).ok();
+        if let Some(canonical_path) = &canonical_path {
+            // A link to a folder containing it is a loop, other folders reached
+            // through several links are skipped after the first time
+            if ancestors.contains(canonical_path) {
+                diagnostics.push(Diagnostic::SkippedFile {
+                    file: self.relative_path(folder_path),
+                    reason: "folder contains itself (symbolic link loop)".to_string(),
+                });
+                return;
+            }
+            if !visited.insert(canonical_path.clone()) {
+                return;
+            }
+            ancestors.push(canonical_path.clone());
+        }
+
+        self.search_entries(folder_path, ancestors, visited, files, diagnostics);
+
+        if canonical_path.is_some() {
+            ancestors.pop();
+        }
+    }
+
+    // Scan the entries of a folder, descending into subfolders
+    fn search_entries(
+        &self,
+        folder_path: &Path,
+        ancestors: &mut Vec<PathBuf>,
+        visited: &mut HashSet<PathBuf>,
+        files: &mut VaultFiles,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let entries = match fs::read_dir(folder_path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                diagnostics.push(Diagnostic::UnreadableFile {
+                    file: self.relative_path(folder_path),
+                    message: error.to_string(),
+                });
+                return;
+            }
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    diagnostics.push(Diagnostic::UnreadableFile {
+                        file: self.relative_path(folder_path),
+                        message: error.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let file_path = entry.path();
+            let is_symlink = entry
+                .file_type()
+                .is_ok_and(|file_type| file_type.is_symlink());
+            let is_dir = file_path.is_dir();
+
+            if (is_symlink && !self.config.follow_symlinks) || self.is_excluded(&file_path, is_dir)
+            {
+                continue;
+            }
+
+            if is_dir {
+                self.search_folder(&file_path, ancestors, visited, files, diagnostics);
+            } else if self.is_included(&file_path) {
+                self.add_file(file_path, files, diagnostics);
+            }
+        }
+    }
+
+    // Add a Markdown file, canvas or attachment to the files found, ignoring other files
+    fn add_file(
+        &self,
+        file_path: PathBuf,
+        files: &mut VaultFiles,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        if file_path.extension() == Some(std::ffi::OsStr::new("md")) {
+            match file_path.file_stem().and_then(|os_str| os_str.to_str()) {
+                Some(_) => {
+                    let title = self
+                        .config
+                        .format
+                        .page_title(&self.relative_path(&file_path));
+                    files.markdown.push((file_path, title))
+                }
+                None => diagnostics.push(Diagnostic::SkippedFile {
+                    file: self.relative_path(&file_path),
+                    reason: "file name is not valid UTF-8".to_string(),
+                }),
+            }
+        } else if file_path.extension() == Some(std::ffi::OsStr::new("canvas")) {
+            files.canvases.push(file_path);
+        } else if is_attachment(&file_path) {
+            files.attachments.push(file_path);
+        }
+    }
+
+    // Check whether a file or folder is excluded from the scan
+    fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let is_hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+        let relative_path = normalize_path(&self.relative_path(path));
+
+        (self.config.skip_hidden && is_hidden)
+            || self.exclude.matched(path, is_dir).is_ignore()
+            || self.settings.excludes(&relative_path)
+            || self.config.format.excludes(&relative_path, is_dir)
+    }
+
+    // Check whether a file matches the include patterns (if any)
+    fn is_included(&self, path: &Path) -> bool {
+        self.include.is_empty()
+            || self
+                .include
+                .matched_path_or_any_parents(path, false)
+                .is_ignore()
+    }
+
+    fn relative_path(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.vault_dir)
+            .unwrap_or(path)
+            .to_path_buf()
+    }
+}
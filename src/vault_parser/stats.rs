@@ -0,0 +1,97 @@
+//! This module gathers statistics about the contents and files of notes
+//!
+//! Words and characters are counted in the rendered text of a note's body, so that
+//! Markdown syntax (`#`, `**`, list markers) does not add to the counts.
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::fs;
+
+use crate::{
+    vault_parser::{frontmatter::split_frontmatter, markdown::LineIndex},
+    Heading, PageStats,
+};
+
+/// Counts the words, characters and tasks of a Markdown file, and collects its headings
+pub fn content_stats(contents: &str) -> PageStats {
+    let (_, body) = split_frontmatter(contents);
+    let body_start = contents.len() - body.len();
+    let index = LineIndex::new(contents);
+
+    let mut stats = PageStats::default();
+    let mut text = String::new();
+    let mut heading: Option<Heading> = None;
+
+    let options = Options::ENABLE_TASKLISTS | Options::ENABLE_TABLES | Options::ENABLE_MATH;
+    for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some(Heading {
+                    level: level as u8,
+                    line: index.position(body_start + range.start).0,
+                    ..Default::default()
+                })
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(mut heading) = heading.take() {
+                    heading.text = heading.text.trim().to_string();
+                    stats.headings.push(heading);
+                }
+                text.push('\n');
+            }
+            Event::Text(inner)
+            | Event::Code(inner)
+            | Event::InlineMath(inner)
+            | Event::DisplayMath(inner) => {
+                if let Some(heading) = &mut heading {
+                    heading.text.push_str(&inner);
+                }
+                text.push_str(&inner);
+            }
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak
+            | Event::End(
+                TagEnd::Paragraph | TagEnd::Item | TagEnd::CodeBlock | TagEnd::TableCell,
+            ) => text.push('\n'),
+            Event::TaskListMarker(true) => stats.completed_tasks += 1,
+            Event::TaskListMarker(false) => stats.open_tasks += 1,
+            _ => {}
+        }
+    }
+
+    stats.words = text.split_whitespace().count();
+    stats.characters = text.chars().filter(|c| *c != '\n').count();
+    stats
+}
+
+/// Fills in the size and timestamps of a page's file
+pub fn add_file_stats(stats: &mut PageStats, metadata: &fs::Metadata) {
+    stats.created = metadata.created().ok();
+    stats.modified = metadata.modified().ok();
+    stats.size = metadata.len();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_statistics() {
+        let stats = content_stats(
+            "---\ntags: [a, b]\n---\n# Plan **for** 2023\n\
+            Some *emphasized* words and a [[Link|link]].\n\n\
+            ## Tasks\n- [ ] open task\n- [x] done\n- [X] also done\n",
+        );
+
+        assert_eq!(
+            stats
+                .headings
+                .iter()
+                .map(|heading| (heading.level, heading.text.as_str(), heading.line))
+                .collect::<Vec<_>>(),
+            vec![(1, "Plan for 2023", 4), (2, "Tasks", 7)]
+        );
+        assert_eq!(stats.words, 15);
+        assert_eq!(stats.characters, 82);
+        assert_eq!((stats.open_tasks, stats.completed_tasks), (1, 2));
+    }
+}
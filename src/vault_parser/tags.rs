@@ -0,0 +1,117 @@
+//! This module extracts inline `#tags` from the body of Markdown files
+
+/// Searches a Markdown body for inline tags (`#tag`, `#nested/tag`).
+///
+/// The body is expected to be masked with [mask_non_prose](super::markdown::mask_non_prose),
+/// so that code, comments and math are skipped. URLs and heading markers are skipped as well.
+/// The returned tags are stripped of their leading `#`.
+pub fn search_inline_tags(body: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+
+        // Skip heading markers, but keep tags in the heading text
+        let text = match trimmed.trim_start_matches('#') {
+            rest if rest.len() < trimmed.len()
+                && trimmed.len() - rest.len() <= 6
+                && (rest.is_empty() || rest.starts_with(' ')) =>
+            {
+                rest
+            }
+            _ => line,
+        };
+
+        for tag in search_line_tags(text) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                tags.push(tag)
+            }
+        }
+    }
+
+    tags
+}
+
+// Search a single line of prose for tags, which start with a `#` at the start of a token
+// or after punctuation (`(#tag)`, `,#tag`), but not within a word (`a#b`)
+fn search_line_tags(line: &str) -> Vec<String> {
+    line.split_whitespace()
+        // Skip URLs, whose fragments would otherwise look like tags
+        .filter(|token| !token.contains("://") && !token.starts_with("www."))
+        .flat_map(|token| {
+            token.char_indices().filter_map(|(i, c)| {
+                let previous = token[..i].chars().next_back();
+                match (c, previous) {
+                    ('#', None) => parse_tag(&token[i + 1..]),
+                    ('#', Some(p))
+                        if p.is_ascii_punctuation() && !matches!(p, '#' | '_' | '/' | '&') =>
+                    {
+                        parse_tag(&token[i + 1..])
+                    }
+                    _ => None,
+                }
+            })
+        })
+        .collect()
+}
+
+// Read a tag name from the characters following a `#`
+fn parse_tag(text: &str) -> Option<String> {
+    let tag: String = text
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+        .collect();
+    let tag = tag.trim_end_matches('/');
+
+    // Tags must contain at least one non-numerical character
+    if tag.is_empty() || tag.chars().all(|c| c.is_numeric() || c == '/') {
+        None
+    } else {
+        Some(tag.to_string())
+    }
+}
+
+/// Checks whether a tag matches a tag query, taking tag hierarchies into account.
+///
+/// The query `project` matches the tags `project` and `project/alpha`, but not `projects`.
+/// Tags are compared case-insensitively.
+pub fn tag_matches(tag: &str, query: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let query = query.trim_start_matches('#').to_lowercase();
+
+    tag == query || tag.starts_with(&(query + "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault_parser::markdown::mask_non_prose;
+
+    #[test]
+    fn inline_tags() {
+        let body = "# Heading #heading-tag\n\
+            Text with #project/alpha and #status/done, but not #123 or a#b.\n\
+            See https://example.com/#anchor and `#code`.\n\
+            ```\n#fenced\n```\n";
+
+        assert_eq!(
+            search_inline_tags(&mask_non_prose(body)),
+            vec!["heading-tag", "project/alpha", "status/done"]
+        );
+    }
+
+    #[test]
+    fn tags_after_punctuation() {
+        assert_eq!(
+            search_inline_tags("Use (#tag) and #tag2.\nLists: #a,#b;#c but not a#b, ##d or &#39;"),
+            vec!["tag", "tag2", "a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn nested_tag_matching() {
+        assert!(tag_matches("project/alpha", "project"));
+        assert!(tag_matches("Project/Alpha", "#project/alpha"));
+        assert!(!tag_matches("projects", "project"));
+    }
+}
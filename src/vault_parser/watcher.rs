@@ -0,0 +1,81 @@
+//! This module watches a vault for changes to its files
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use crate::vault_parser::scanner::{is_attachment, ScanConfig, VaultScanner};
+
+/// This struct watches a vault folder (recursively) for created, modified, renamed and deleted files.
+pub struct VaultWatcher {
+    vault_dir: PathBuf,
+    /// Decides which changed files are part of the vault
+    scanner: VaultScanner,
+    /// The underlying watcher, which stops watching when dropped
+    _watcher: RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<Event>>,
+}
+
+impl VaultWatcher {
+    /// Starts watching a vault folder, reporting changes to the files scanned with `config`
+    pub fn new(vault_dir: &Path, config: &ScanConfig) -> notify::Result<Self> {
+        // Events carry absolute paths, which are made relative to the canonical vault path
+        let vault_dir = fs::canonicalize(vault_dir)?;
+        // Problems with the settings are reported when the vault is loaded
+        let scanner = VaultScanner::new(&vault_dir, config, &mut Vec::new());
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            sender.send(event).ok();
+        })?;
+        watcher.watch(&vault_dir, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            vault_dir,
+            scanner,
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Returns the vault-relative paths of the Markdown files, canvases, attachments and folders changed
+    /// since the last call.
+    ///
+    /// Both the old and the new path of renamed files are returned. Changes to other files
+    /// (such as the `.obsidian` settings) and to files excluded from scans are left out.
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = Vec::new();
+
+        for event in self.events.try_iter().filter_map(Result::ok) {
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+
+            for path in event.paths {
+                // Folders may have been deleted already, so missing paths without extension
+                // are kept as well
+                let relevant = match path.extension() {
+                    Some(extension) => {
+                        extension == "md" || extension == "canvas" || is_attachment(&path)
+                    }
+                    None => path.is_dir() || !path.exists(),
+                };
+                let Ok(relative_path) = path.strip_prefix(&self.vault_dir) else {
+                    continue;
+                };
+
+                if relevant
+                    && !self.scanner.excludes(relative_path)
+                    && !paths.iter().any(|p| p == relative_path)
+                {
+                    paths.push(relative_path.to_path_buf())
+                }
+            }
+        }
+
+        paths
+    }
+}